@@ -1,7 +1,7 @@
 use proc_macro2::{Ident, TokenStream};
 
 use quote::{format_ident, ToTokens};
-use syn::{Data, DataStruct, Field, Fields, Meta, NestedMeta, Type};
+use syn::{Data, DataStruct, Field, Fields, Lit, Meta, NestedMeta, Type};
 use syn::{ImplGenerics, TypeGenerics, WhereClause};
 
 #[allow(dead_code)]
@@ -10,29 +10,23 @@ fn is_rdbc_orm_column(field: &Field) -> Option<TokenStream> {
         Type::Path(path) => {
             if let Some(seg) = path.path.segments.last() {
                 if seg.ident == "Column" {
-                    match &seg.arguments {
-                        syn::PathArguments::AngleBracketed(args) => {
-                            return Some(args.into_token_stream());
-                        }
-
-                        _ => {}
+                    if let syn::PathArguments::AngleBracketed(args) = &seg.arguments {
+                        return Some(args.into_token_stream());
                     }
                 }
             }
 
-            return None;
+            None
         }
-        _ => return None,
+        _ => None,
     }
 }
 
 /// Extract table name from table attrs
 pub fn extract_column_name(field: &Field) -> anyhow::Result<String> {
     for attr in &field.attrs {
-        if let Some(path) = attr.path.get_ident() {
-            if path != "col_name" {
-                continue;
-            }
+        if !attr.path.is_ident("col_name") {
+            continue;
         }
 
         let meta: NestedMeta = attr.parse_args()?;
@@ -41,13 +35,14 @@ pub fn extract_column_name(field: &Field) -> anyhow::Result<String> {
             NestedMeta::Meta(Meta::Path(table_name)) => {
                 return Ok(format!("{}", table_name.get_ident().unwrap()));
             }
+            NestedMeta::Lit(Lit::Str(table_name)) => return Ok(table_name.value()),
             _ => {}
         }
     }
 
     let field_name = field.ident.as_ref().unwrap();
 
-    return Ok(field_name.to_string());
+    Ok(field_name.to_string())
 }
 
 /// extract table fields token stream
@@ -61,10 +56,9 @@ fn extract_table_columns(data: &Data) -> anyhow::Result<Vec<TokenStream>> {
                     .map(|field| {
                         let field_name = field.ident.as_ref().unwrap();
 
-                        let column_type_path = is_rdbc_orm_column(field).expect(&format!(
-                            "table field '{}' type must be rdbc_orm::Column",
-                            field_name
-                        ));
+                        let column_type_path = is_rdbc_orm_column(field).unwrap_or_else(|| {
+                            panic!("table field '{}' type must be rdbc_orm::Column", field_name)
+                        });
 
                         let column_name = extract_column_name(field).unwrap();
 
@@ -0,0 +1,109 @@
+use proc_macro2::{Ident, TokenStream};
+
+use syn::{Attribute, Data, DataStruct, Expr, Fields};
+use syn::{ImplGenerics, TypeGenerics, WhereClause};
+
+use crate::col::extract_column_name;
+
+/// Extract the `col_default` attribute, `#[col_default]` uses [`Default::default`]
+fn extract_default(attrs: &[Attribute]) -> anyhow::Result<Option<TokenStream>> {
+    for attr in attrs {
+        if !attr.path.is_ident("col_default") {
+            continue;
+        }
+
+        if attr.tokens.is_empty() {
+            return Ok(Some(quote::quote! { ::std::default::Default::default() }));
+        }
+
+        let expr: Expr = attr.parse_args()?;
+
+        return Ok(Some(quote::quote! { #expr }));
+    }
+
+    Ok(None)
+}
+
+/// Expand the read expression of one field, `pos` is the column name or index
+fn expand_field_value(pos: TokenStream, attrs: &[Attribute]) -> anyhow::Result<TokenStream> {
+    let expanded = match extract_default(attrs)? {
+        Some(default) => quote::quote! {
+            match row.get(#pos) {
+                None | Some(rdbc::Value::Null) => #default,
+                Some(value) => rdbc::FromValue::from_value(value)?,
+            }
+        },
+        None => quote::quote! {
+            row.try_get(#pos)?
+        },
+    };
+
+    Ok(expanded)
+}
+
+pub fn expand_from_row(
+    struct_name: &Ident,
+    impl_generics: &ImplGenerics,
+    ty_generics: &TypeGenerics,
+    where_clause: Option<&WhereClause>,
+    data: &Data,
+) -> anyhow::Result<TokenStream> {
+    let body = match data {
+        Data::Struct(DataStruct { fields, .. }) => match fields {
+            // named fields are mapped by column name
+            Fields::Named(fields_named) => {
+                let fields = fields_named
+                    .named
+                    .iter()
+                    .map(|field| {
+                        let field_name = field.ident.as_ref().unwrap();
+
+                        let column_name = extract_column_name(field)?;
+
+                        let value =
+                            expand_field_value(quote::quote! { #column_name }, &field.attrs)?;
+
+                        Ok(quote::quote! { #field_name: #value, })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                quote::quote! { #struct_name { #(#fields)* } }
+            }
+            // tuple fields are mapped by column index
+            Fields::Unnamed(fields_unnamed) => {
+                let fields = fields_unnamed
+                    .unnamed
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| {
+                        let index = index as u64;
+
+                        let value = expand_field_value(quote::quote! { #index }, &field.attrs)?;
+
+                        Ok(quote::quote! { #value, })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                quote::quote! { #struct_name ( #(#fields)* ) }
+            }
+            Fields::Unit => quote::quote! { #struct_name },
+        },
+
+        _ => {
+            return Err(anyhow::anyhow!(
+                "sorry, FromRow is not implemented for union or enum type."
+            ));
+        }
+    };
+
+    let expanded = quote::quote! {
+        impl #impl_generics rdbc::FromRow for #struct_name #ty_generics
+        #where_clause {
+            fn from_row(row: &rdbc::Row) -> rdbc::Result<Self> {
+                Ok(#body)
+            }
+        }
+    };
+
+    Ok(expanded)
+}
@@ -62,28 +62,22 @@ fn extract_idx_name(field_name: &str, attrs: &Vec<Attribute>) -> anyhow::Result<
 
         let meta = meta.unwrap();
 
-        match meta {
-            NestedMeta::Meta(Meta::Path(path)) => {
-              
-                if ident == "col_unique" {
-                    return Ok(Some((
-                        format!("{}", path.get_ident().unwrap()),
-                        quote::quote! { rdbc_orm::schema::IndexType::Unique },
-                    )));
-                } else {
-                    return Ok(Some((
-                        format!("{}", path.get_ident().unwrap()),
-                        quote::quote! { rdbc_orm::schema::IndexType::Index },
-                    )));
-                }
-            }
-            _ => {
-
+        if let NestedMeta::Meta(Meta::Path(path)) = meta {
+            if ident == "col_unique" {
+                return Ok(Some((
+                    format!("{}", path.get_ident().unwrap()),
+                    quote::quote! { rdbc_orm::schema::IndexType::Unique },
+                )));
+            } else {
+                return Ok(Some((
+                    format!("{}", path.get_ident().unwrap()),
+                    quote::quote! { rdbc_orm::schema::IndexType::Index },
+                )));
             }
         }
     }
 
-    return Ok(None);
+    Ok(None)
 }
 
 /// extract table fields token stream
@@ -101,13 +95,13 @@ fn extract_table_idxs(data: &Data) -> anyhow::Result<Vec<TokenStream>> {
                         Some((name, index_type)) => {
                             idx_types.insert(name.clone(), index_type);
                             
-                            idxs.entry(name.clone()).or_insert_with(|| vec![]).push(field_name.clone());
+                            idxs.entry(name.clone()).or_default().push(field_name.clone());
                         }
                         None => continue,
                     }
                 }
 
-                return Ok(idxs.iter().map(|(k, field_names)| {
+                Ok(idxs.iter().map(|(k, field_names)| {
             
                     let idx_type = idx_types.get(k).unwrap();
 
@@ -120,18 +114,18 @@ fn extract_table_idxs(data: &Data) -> anyhow::Result<Vec<TokenStream>> {
                             &idx_def
                         }
                     }
-                }).collect::<Vec<_>>());
+                }).collect::<Vec<_>>())
             } else {
-                return Err(anyhow::anyhow!("sorry, may it's a complicated struct."));
+                Err(anyhow::anyhow!("sorry, may it's a complicated struct."))
             }
         }
 
         _ => {
-            return Err(anyhow::anyhow!(
+            Err(anyhow::anyhow!(
                 "sorry, Show is not implemented for union or enum type."
-            ));
+            ))
         }
-    };
+    }
 }
 
 pub fn expand_idx_methods(
@@ -1,6 +1,7 @@
 mod col;
 mod der;
 mod fields;
+mod from_row;
 mod idx;
 mod impl_body;
 mod ser;
@@ -10,6 +11,7 @@ use impl_body::impl_body;
 use proc_macro::*;
 use syn::{parse_macro_input, DeriveInput};
 
+use crate::from_row::expand_from_row;
 use crate::table_name::table_name;
 
 #[proc_macro_derive(
@@ -34,23 +36,11 @@ pub fn rdbc_orm_derive(input: TokenStream) -> TokenStream {
 
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let table_name_expanded = table_name(
-        &ident,
-        &impl_generics,
-        &ty_generics,
-        where_clause.clone(),
-        &attrs,
-    )
-    .unwrap();
+    let table_name_expanded =
+        table_name(&ident, &impl_generics, &ty_generics, where_clause, &attrs).unwrap();
 
-    let impl_body_expanded = impl_body(
-        &ident,
-        &impl_generics,
-        &ty_generics,
-        where_clause.clone(),
-        &data,
-    )
-    .unwrap();
+    let impl_body_expanded =
+        impl_body(&ident, &impl_generics, &ty_generics, where_clause, &data).unwrap();
 
     let expanded = quote::quote! {
         #impl_body_expanded
@@ -64,3 +54,24 @@ pub fn rdbc_orm_derive(input: TokenStream) -> TokenStream {
 
     expanded.into()
 }
+
+/// Implement `rdbc::FromRow`, named fields are read by column name
+/// (`#[col_name(..)]` renames) and tuple fields by column index.
+///
+/// Use `Option<T>` for nullable columns, `#[col_default]` or `#[col_default(expr)]`
+/// for columns which may be missing or `NULL`.
+#[proc_macro_derive(FromRow, attributes(col_name, col_default))]
+pub fn rdbc_from_row_derive(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        ident,
+        data,
+        generics,
+        ..
+    } = parse_macro_input!(input as DeriveInput);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    expand_from_row(&ident, &impl_generics, &ty_generics, where_clause, &data)
+        .unwrap()
+        .into()
+}
@@ -12,15 +12,12 @@ pub fn extract_table_name(attrs: &Vec<Attribute>) -> anyhow::Result<Option<Strin
 
         let meta: NestedMeta = attr.parse_args()?;
 
-        match meta {
-            NestedMeta::Meta(Meta::Path(table_name)) => {
-                return Ok(Some(format!("{}", table_name.get_ident().unwrap())));
-            }
-            _ => {}
+        if let NestedMeta::Meta(Meta::Path(table_name)) = meta {
+            return Ok(Some(format!("{}", table_name.get_ident().unwrap())));
         }
     }
 
-    return Ok(None);
+    Ok(None)
 }
 
 pub fn table_name(
@@ -44,5 +41,5 @@ pub fn table_name(
         }
     };
 
-    Ok(expanded.into())
+    Ok(expanded)
 }
@@ -109,7 +109,7 @@ where
             return Ok(Some(Column { _data: Some(t) }));
         }
 
-        return Ok(None);
+        Ok(None)
     }
 }
 
@@ -120,7 +120,7 @@ macro_rules! declare_col_int_type {
             where
                 S: Serializer,
             {
-                s.write_rdbc_value(col, rdbc::Value::I64((*self) as i64))
+                s.write_rdbc_value(col, rdbc::ToValue::to_value(self)?)
             }
         }
 
@@ -129,8 +129,8 @@ macro_rules! declare_col_int_type {
             where
                 D: Deserializer,
             {
-                if let Some(rdbc::Value::I64(i)) = der.read_rdbc_value(col)? {
-                    return Ok(Some(i as $t));
+                if let Some(value @ rdbc::Value::I64(_)) = der.read_rdbc_value(col)? {
+                    return Ok(Some(rdbc::FromValue::from_value(&value)?));
                 }
 
                 Ok(None)
@@ -154,7 +154,7 @@ macro_rules! declare_col_float_type {
             where
                 S: Serializer,
             {
-                s.write_rdbc_value(col, rdbc::Value::F64((*self) as f64))
+                s.write_rdbc_value(col, rdbc::ToValue::to_value(self)?)
             }
         }
 
@@ -163,8 +163,8 @@ macro_rules! declare_col_float_type {
             where
                 D: Deserializer,
             {
-                if let Some(rdbc::Value::F64(i)) = der.read_rdbc_value(col)? {
-                    return Ok(Some(i as $t));
+                if let Some(value @ rdbc::Value::F64(_)) = der.read_rdbc_value(col)? {
+                    return Ok(Some(rdbc::FromValue::from_value(&value)?));
                 }
 
                 Ok(None)
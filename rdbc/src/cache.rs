@@ -0,0 +1,161 @@
+use std::{
+    collections::VecDeque,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use super::driver;
+use super::error::Result;
+
+/// Hit/miss counters shared by the statement caches of one pool
+#[derive(Default)]
+pub(crate) struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounters {
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// LRU cache of the prepared statements of one connection, keyed by SQL text.
+///
+/// A statement in use is taken out of the cache, and put back when released,
+/// so the same query prepared twice at the same time gets two statements.
+pub(crate) struct StatementCache {
+    capacity: usize,
+    /// Least recently used first
+    entries: VecDeque<(String, Box<dyn driver::Statement>)>,
+    counters: Arc<CacheCounters>,
+}
+
+impl StatementCache {
+    pub(crate) fn new(capacity: usize, counters: Arc<CacheCounters>) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+            counters,
+        }
+    }
+
+    /// Take the cached statement of `query`, if any
+    pub(crate) fn take(&mut self, query: &str) -> Option<Box<dyn driver::Statement>> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        match self.entries.iter().rposition(|(sql, _)| sql == query) {
+            Some(index) => {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+
+                self.entries.remove(index).map(|(_, statement)| statement)
+            }
+            None => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+
+                None
+            }
+        }
+    }
+
+    /// Close all the cached statements, which may refer to rolled back schema changes
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Put back a released statement as the most recently used one,
+    /// evicting the least recently used statements beyond capacity.
+    pub(crate) fn put(&mut self, query: String, statement: Box<dyn driver::Statement>) {
+        if self.capacity == 0 || self.entries.iter().any(|(sql, _)| *sql == query) {
+            return;
+        }
+
+        self.entries.push_back((query, statement));
+
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Driver statement put back in its [`StatementCache`] when dropped
+pub(crate) struct CachedStatement {
+    /// Always `Some` until dropped
+    statement: Option<Box<dyn driver::Statement>>,
+    query: String,
+    cache: Arc<Mutex<StatementCache>>,
+}
+
+impl CachedStatement {
+    /// Take the statement of `query` from `cache`, or prepare it with `prepare`,
+    /// then apply `timeout`.
+    pub(crate) async fn prepare<F>(
+        cache: Arc<Mutex<StatementCache>>,
+        query: &str,
+        timeout: Option<Duration>,
+        prepare: F,
+    ) -> Result<Self>
+    where
+        F: FnOnce() -> driver::Prepare,
+    {
+        let cached = cache.lock().unwrap().take(query);
+
+        let statement = match cached {
+            Some(mut statement) => {
+                // the previous user may have changed the timeout
+                statement.set_timeout(timeout);
+
+                statement
+            }
+            None => {
+                let mut statement = prepare().await?;
+
+                if timeout.is_some() {
+                    statement.set_timeout(timeout);
+                }
+
+                statement
+            }
+        };
+
+        Ok(Self {
+            statement: Some(statement),
+            query: query.to_owned(),
+            cache,
+        })
+    }
+}
+
+impl Deref for CachedStatement {
+    type Target = dyn driver::Statement;
+
+    fn deref(&self) -> &Self::Target {
+        self.statement.as_deref().unwrap()
+    }
+}
+
+impl DerefMut for CachedStatement {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.statement.as_deref_mut().unwrap()
+    }
+}
+
+impl Drop for CachedStatement {
+    fn drop(&mut self) {
+        if let Some(statement) = self.statement.take() {
+            let query = std::mem::take(&mut self.query);
+
+            self.cache.lock().unwrap().put(query, statement);
+        }
+    }
+}
@@ -1,19 +1,23 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use super::driver;
+use futures::{future::BoxFuture, FutureExt};
+
+use super::cache::CachedStatement;
+use super::driver::{self, TransactionOptions};
+use super::error::Result;
+use super::pool::*;
 use super::statement::*;
 use super::transaction::*;
-use anyhow::*;
+use super::waker;
 
 #[derive(Clone)]
 pub struct Database {
-    name: String,
-    url: String,
-    drivers: Arc<Mutex<HashMap<String, Box<dyn driver::Driver>>>>,
-    connection_pool: Arc<Mutex<HashMap<String, Box<dyn driver::Connection>>>>,
+    pool: Arc<Pool>,
+    query_timeout: Option<Duration>,
 }
 
 impl Database {
@@ -21,78 +25,171 @@ impl Database {
         name: &str,
         url: &str,
         drivers: Arc<Mutex<HashMap<String, Box<dyn driver::Driver>>>>,
+        options: PoolOptions,
     ) -> Self {
         Self {
-            name: name.to_owned(),
-            url: url.to_owned(),
-            drivers,
-            connection_pool: Default::default(),
+            pool: Pool::new(name, url, drivers, options),
+            query_timeout: None,
         }
     }
 
-    async fn select_one_connection(&mut self) -> Result<Box<dyn driver::Connection>> {
-        let mut connection = {
-            let mut connections = self.connection_pool.lock().unwrap();
+    /// Default run time limit of each execute, query and row fetch of the
+    /// statements prepared by this database and its transactions,
+    /// see [`Statement::set_timeout`]. Clones share the pool but not this setting.
+    pub fn set_query_timeout(&mut self, timeout: Option<Duration>) {
+        self.query_timeout = timeout;
+    }
 
-            let mut id: Option<String> = None;
+    pub fn query_timeout(&self) -> Option<Duration> {
+        self.query_timeout
+    }
 
-            for (k, v) in connections.iter() {
-                if v.is_valid() {
-                    id = Some(k.to_owned());
-                    break;
-                }
-            }
+    /// Returns the connection pool configuration of this database.
+    pub fn pool_options(&self) -> &PoolOptions {
+        self.pool.options()
+    }
 
-            match id {
-                Some(id) => connections.remove(&id),
-                _ => None,
-            }
-        };
+    /// Returns the current connection pool counters.
+    pub fn pool_status(&self) -> PoolStatus {
+        self.pool.status()
+    }
 
-        if connection.is_none() {
-            let fut = {
-                let mut drivers = self.drivers.lock().unwrap();
+    /// Returns the prepared statement cache counters of all the pool connections.
+    pub fn statement_cache_status(&self) -> StatementCacheStatus {
+        self.pool.statement_cache_status()
+    }
 
-                if let Some(driver) = drivers.get_mut(&self.name) {
-                    Some(driver.open(&self.url))
-                } else {
-                    None
-                }
-            };
+    /// Prepare creates a prepared statement for later queries or executions.
+    pub async fn prepare(&mut self, query: &str) -> Result<Statement<'static>> {
+        let mut connection = self.pool.acquire().await?;
+
+        let statement = CachedStatement::prepare(
+            connection.statement_cache(),
+            query,
+            self.query_timeout,
+            || connection.inner().prepare(query),
+        )
+        .await?;
+
+        Ok(Statement::new(Some(connection), statement))
+    }
 
-            if fut.is_none() {
-                return Err(anyhow::anyhow!("driver {} not found", self.name));
-            }
+    /// Runs every statement of `script` in order, see [`driver::Connection::execute_script`].
+    ///
+    /// Each statement runs in its own implicit transaction, unless the script
+    /// handles transactions itself, see [`Database::execute_script_in_transaction`].
+    pub async fn execute_script(&mut self, script: &str) -> Result<driver::ExecuteResult> {
+        let mut connection = self.pool.acquire().await?;
 
-            connection = Some(fut.unwrap().await?);
-        }
+        let execute = connection.inner().execute_script(script);
 
-        Ok(connection.unwrap())
+        execute.await
     }
 
-    /// Prepare creates a prepared statement for later queries or executions.
-    pub async fn prepare(&mut self, query: &str) -> Result<Statement> {
-        let mut connection = self.select_one_connection().await?;
+    /// Runs every statement of `script` in a transaction, which commits only
+    /// if all the statements succeed. Retried like [`Database::transaction`].
+    pub async fn execute_script_in_transaction(
+        &mut self,
+        script: &str,
+    ) -> Result<driver::ExecuteResult> {
+        let script = script.to_owned();
 
-        let statement = connection.prepare(query).await?;
+        self.transaction(move |tx| {
+            let script = script.clone();
 
-        Ok(Statement::new(
-            self.connection_pool.clone(),
-            Some(connection),
-            statement,
-        ))
+            async move { tx.execute_script(&script).await }.boxed()
+        })
+        .await
     }
 
     /// Starts and returns a new transaction.
     pub async fn begin(&mut self) -> Result<Transaction> {
-        let mut connection = self.select_one_connection().await?;
+        self.begin_with(Default::default()).await
+    }
+
+    /// Starts and returns a new transaction with `options`.
+    pub async fn begin_with(&mut self, options: TransactionOptions) -> Result<Transaction> {
+        let mut connection = self.pool.acquire().await?;
 
-        let tx = connection.begin().await?;
+        let tx = connection.inner().begin(options).await?;
 
-        Ok(Transaction::new(
-            self.connection_pool.clone(),
-            Some(connection),
-            tx,
-        ))
+        Ok(Transaction::new(connection, tx, self.query_timeout))
+    }
+
+    /// Run `f` in a transaction, see [`Database::transaction_with`].
+    pub async fn transaction<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: for<'c> FnMut(&'c mut Transaction) -> BoxFuture<'c, Result<T>>,
+    {
+        self.transaction_with(Default::default(), Default::default(), f)
+            .await
+    }
+
+    /// Run `f` in a transaction started with `options`.
+    ///
+    /// The transaction commits if `f` returns `Ok`, and rolls back if it returns
+    /// `Err` or panics. The whole transaction, `f` included, is retried according
+    /// to `policy` while beginning, running `f` or committing fails with a transient error.
+    ///
+    /// ```ignore
+    /// let id = db
+    ///     .transaction(|tx| {
+    ///         async move {
+    ///             let mut stmt = tx.prepare("INSERT INTO t(name) VALUES(?)").await?;
+    ///
+    ///             Ok(stmt.execute(params!["hello"]).await?.last_insert_id)
+    ///         }
+    ///         .boxed()
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn transaction_with<F, T>(
+        &mut self,
+        options: TransactionOptions,
+        policy: RetryPolicy,
+        mut f: F,
+    ) -> Result<T>
+    where
+        F: for<'c> FnMut(&'c mut Transaction) -> BoxFuture<'c, Result<T>>,
+    {
+        let mut retry = 0;
+
+        loop {
+            match self.try_transaction(options.clone(), &mut f).await {
+                Err(err) if err.is_transient() && retry < policy.max_retries => {
+                    let backoff = policy.backoff(retry);
+
+                    log::debug!("retry transaction in {:?}: {}", backoff, err);
+
+                    waker::delay(backoff).await;
+
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn try_transaction<F, T>(&mut self, options: TransactionOptions, f: &mut F) -> Result<T>
+    where
+        F: for<'c> FnMut(&'c mut Transaction) -> BoxFuture<'c, Result<T>>,
+    {
+        let mut tx = self.begin_with(options).await?;
+
+        // a panic in `f` drops `tx`, which rolls it back
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+
+                Ok(value)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    log::error!("rollback transaction failed: {}", rollback_err);
+                }
+
+                Err(err)
+            }
+        }
     }
 }
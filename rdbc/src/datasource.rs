@@ -3,12 +3,19 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use super::database::*;
+use super::pool::{PoolError, PoolOptions};
 
 pub struct DataSource {
     drivers: Arc<Mutex<HashMap<String, Box<dyn driver::Driver>>>>,
 }
 
-use anyhow::*;
+use super::error::{Error, ErrorKind, Result};
+
+impl Default for DataSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl DataSource {
     pub fn new() -> Self {
@@ -25,7 +32,10 @@ impl DataSource {
         let mut drivers = self.drivers.lock().unwrap();
 
         if drivers.contains_key(name) {
-            return Err(anyhow!("register driver {} twice", name));
+            return Err(Error::msg(
+                ErrorKind::Other,
+                format!("register driver {} twice", name),
+            ));
         }
 
         drivers.insert(name.to_owned(), Box::new(driver));
@@ -33,17 +43,27 @@ impl DataSource {
         Ok(())
     }
 
+    /// Removes the driver and shuts it down, see [`driver::Driver::shutdown`]
     pub fn unregister_driver(&mut self, name: &str) -> Result<()> {
-        self.drivers.lock().unwrap().remove(name);
+        let driver = self.drivers.lock().unwrap().remove(name);
 
-        Ok(())
+        match driver {
+            Some(mut driver) => driver.shutdown(),
+            None => Ok(()),
+        }
     }
 
+    /// Open database with default [`PoolOptions`]
     pub fn open(&mut self, name: &str, url: &str) -> Result<Database> {
+        self.open_with(name, url, PoolOptions::default())
+    }
+
+    /// Open database, using `options` to configure its connection pool
+    pub fn open_with(&mut self, name: &str, url: &str, options: PoolOptions) -> Result<Database> {
         if !self.drivers.lock().unwrap().contains_key(name) {
-            return Err(anyhow!("driver {} not found", name));
+            return Err(PoolError::DriverNotFound(name.to_owned()).into());
         }
 
-        Ok(Database::new(name, url, self.drivers.clone()))
+        Ok(Database::new(name, url, self.drivers.clone(), options))
     }
 }
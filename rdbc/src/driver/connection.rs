@@ -1,20 +1,67 @@
+use std::fmt::Display;
+
+use super::ExecuteResult;
 use super::Statement;
 use super::Transaction;
+use super::TransactionOptions;
+use crate::error::Result;
 use crate::waker;
-use anyhow::*;
 
 pub type Prepare = waker::WakableFuture<Result<Box<dyn Statement>>>;
 pub type Begin = waker::WakableFuture<Result<Box<dyn Transaction>>>;
+pub type Ping = waker::WakableFuture<Result<()>>;
+pub type Reset = waker::WakableFuture<Result<bool>>;
+pub type ExecuteScript = waker::WakableFuture<Result<ExecuteResult>>;
 
 pub trait Connection: Send {
     /// Returns a prepared statement, bound to this connection.
     fn prepare(&mut self, query: &str) -> Prepare;
 
-    fn begin(&mut self) -> Begin;
+    /// Runs every statement of `script` in order, ignoring the rows they return.
+    ///
+    /// Returns the rows affected by the whole script and the last insert id.
+    /// Stops at the first failed statement, the error context is its [`ScriptPosition`].
+    fn execute_script(&mut self, script: &str) -> ExecuteScript;
+
+    /// Starts a transaction, see [`TransactionOptions`].
+    fn begin(&mut self, options: TransactionOptions) -> Begin;
 
     /// Driver use this function to return connection status
     fn is_valid(&self) -> bool;
 
+    /// Check the connection still works by a round trip to the database,
+    /// the pool evicts the connection if this returns an error.
+    fn ping(&mut self) -> Ping;
+
+    /// Restore the session state (open transaction, temporary objects, session settings)
+    /// before the pool reuses this connection.
+    ///
+    /// Resolves to `true` if the driver had to clean something up.
+    fn reset(&mut self) -> Reset;
+
     /// Get connection id
     fn id(&self) -> &str;
 }
+
+/// Position of the failed statement of a script, see [`Connection::execute_script`]
+///
+/// Get it from the error with `err.downcast_ref::<ScriptPosition>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptPosition {
+    /// Index of the statement in the script, starting from 0
+    pub index: usize,
+    /// Byte offset of the statement in the script
+    pub offset: usize,
+    /// Line of the statement in the script, starting from 1
+    pub line: usize,
+}
+
+impl Display for ScriptPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "script statement {} at line {} (offset {}) failed",
+            self.index, self.line, self.offset
+        )
+    }
+}
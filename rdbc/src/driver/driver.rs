@@ -1,9 +1,16 @@
+use crate::error::Result;
 use crate::waker;
-use anyhow::*;
 
 pub type Connector = waker::WakableFuture<Result<Box<dyn super::Connection>>>;
 
 pub trait Driver: Send {
     /// Open returns new connection to the database
     fn open(&mut self, name: &str) -> Connector;
+
+    /// Stops the driver and releases its resources, e.g. joins its threads.
+    ///
+    /// Connections opened by the driver fail afterwards, the default does nothing.
+    fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
@@ -1,6 +1,8 @@
 mod connection;
+#[allow(clippy::module_inception)]
 mod driver;
 mod statement;
+pub mod threaded;
 mod transaction;
 
 pub use connection::*;
@@ -8,11 +10,11 @@ pub use driver::*;
 pub use statement::*;
 pub use transaction::*;
 
-use crate::waker;
+use crate::waker::{self, SharedWaker};
 
-use anyhow::*;
+use crate::error::{Error, Result};
 
-/// Driver async tasks
+/// Driver async tasks, see [`threaded`]
 pub enum Task {
     /// Prepare(id, query, waker)
     Prepare(
@@ -20,18 +22,48 @@ pub enum Task {
         String,
         waker::SharedWaker<Result<Box<dyn Statement>>>,
     ),
-    /// (id, waker)
-    Begin(String, waker::SharedWaker<Result<Box<dyn Transaction>>>),
+    /// (id, options, waker)
+    Begin(
+        String,
+        TransactionOptions,
+        waker::SharedWaker<Result<Box<dyn Transaction>>>,
+    ),
+
+    /// Run script (connection id, script, waker)
+    ExecuteScript(String, String, waker::SharedWaker<Result<ExecuteResult>>),
 
     /// Open new connection (url, waker)
     Open(String, waker::SharedWaker<Result<Box<dyn Connection>>>),
 
+    /// Check connection health (connection id, waker)
+    Ping(String, waker::SharedWaker<Result<()>>),
+
+    /// Reset connection session state (connection id, waker)
+    Reset(String, waker::SharedWaker<Result<bool>>),
+
     /// (stmt id, args, waker)
     Execute(String, Vec<Arg>, waker::SharedWaker<Result<ExecuteResult>>),
 
+    /// (stmt id, batch args, waker)
+    ExecuteBatch(
+        String,
+        Vec<Vec<Arg>>,
+        waker::SharedWaker<Result<Vec<ExecuteResult>>>,
+    ),
+
+    /// Set statement timeout (stmt id, timeout)
+    SetTimeout(String, Option<std::time::Duration>),
+
     /// (stmt id, args, waker)
     Query(String, Vec<Arg>, waker::SharedWaker<Result<Box<dyn Rows>>>),
 
+    /// (stmt id, args, waker)
+    ExecuteReturning(
+        String,
+        Vec<Arg>,
+        waker::SharedWaker<Result<(ExecuteResult, Box<dyn Rows>)>>,
+    ),
+
     /// (resultset id, waker)
     Columns(String, waker::SharedWaker<Result<Vec<ColumnMetaData>>>),
 
@@ -46,6 +78,12 @@ pub enum Task {
         waker::SharedWaker<Result<Value>>,
     ),
 
+    /// Current row get all values (resultset id, waker)
+    RowsGetRow(String, waker::SharedWaker<Result<Vec<Value>>>),
+
+    /// Iterate and get up to n rows (resultset id, n, waker)
+    RowsNextBatch(String, usize, waker::SharedWaker<Result<Vec<Vec<Value>>>>),
+
     /// Transaction prepare (tx id, query, waker)
     TxPrepare(
         String,
@@ -53,12 +91,24 @@ pub enum Task {
         waker::SharedWaker<Result<Box<dyn Statement>>>,
     ),
 
+    /// Transaction run script (tx id, script, waker)
+    TxExecuteScript(String, String, waker::SharedWaker<Result<ExecuteResult>>),
+
     /// Commit tx (tx id,waker)
     Commit(String, waker::SharedWaker<Result<()>>),
 
     /// Rollback tx (tx id,waker)
     Rollback(String, waker::SharedWaker<Result<()>>),
 
+    /// Create savepoint (tx id, savepoint name, waker)
+    Savepoint(String, String, waker::SharedWaker<Result<()>>),
+
+    /// Release savepoint (tx id, savepoint name, waker)
+    Release(String, String, waker::SharedWaker<Result<()>>),
+
+    /// Rollback to savepoint (tx id, savepoint name, waker)
+    RollbackTo(String, String, waker::SharedWaker<Result<()>>),
+
     /// Close connection (connection id)
     CloseConnection(String),
 
@@ -71,3 +121,59 @@ pub enum Task {
     /// Close resultset (resultset id)
     CloseRows(String),
 }
+
+/// Function resolving a task future with an error, see [`Task::failer`]
+pub type TaskFailer = Box<dyn FnOnce(Error) + Send>;
+
+fn failer<T: Send + 'static>(waker: &SharedWaker<Result<T>>) -> TaskFailer {
+    let waker = waker.clone();
+
+    Box::new(move |err| {
+        // the waker may be poisoned or ready if the driver panicked while waking it
+        let mut waker = waker.lock().unwrap_or_else(|err| err.into_inner());
+
+        if waker.output.is_none() {
+            waker.ready(Err(err));
+        }
+    })
+}
+
+impl Task {
+    /// Resolves the task future with `err` instead of running the task,
+    /// tasks without a future are dropped.
+    pub fn fail(self, err: Error) {
+        self.failer()(err)
+    }
+
+    /// Returns a function that resolves the task future with an error,
+    /// used to fail a task that didn't complete, e.g. the driver panicked running it.
+    pub fn failer(&self) -> TaskFailer {
+        match self {
+            Task::Prepare(_, _, waker) | Task::TxPrepare(_, _, waker) => failer(waker),
+            Task::Begin(_, _, waker) => failer(waker),
+            Task::ExecuteScript(_, _, waker) | Task::TxExecuteScript(_, _, waker) => failer(waker),
+            Task::Open(_, waker) => failer(waker),
+            Task::Ping(_, waker) => failer(waker),
+            Task::Reset(_, waker) => failer(waker),
+            Task::Execute(_, _, waker) => failer(waker),
+            Task::ExecuteBatch(_, _, waker) => failer(waker),
+            Task::Query(_, _, waker) => failer(waker),
+            Task::ExecuteReturning(_, _, waker) => failer(waker),
+            Task::Columns(_, waker) => failer(waker),
+            Task::RowsNext(_, waker) => failer(waker),
+            Task::RowsGet(_, _, _, waker) => failer(waker),
+            Task::RowsGetRow(_, waker) => failer(waker),
+            Task::RowsNextBatch(_, _, waker) => failer(waker),
+            Task::Commit(_, waker)
+            | Task::Rollback(_, waker)
+            | Task::Savepoint(_, _, waker)
+            | Task::Release(_, _, waker)
+            | Task::RollbackTo(_, _, waker) => failer(waker),
+            Task::SetTimeout(..)
+            | Task::CloseConnection(_)
+            | Task::CloseTx(_)
+            | Task::CloseStmt(_)
+            | Task::CloseRows(_) => Box::new(|_| {}),
+        }
+    }
+}
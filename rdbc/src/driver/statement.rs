@@ -1,11 +1,26 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::error::Result;
 use crate::waker;
-use anyhow::*;
 
 pub type Execute = waker::WakableFuture<Result<ExecuteResult>>;
+pub type ExecuteBatch = waker::WakableFuture<Result<Vec<ExecuteResult>>>;
 pub type Query = waker::WakableFuture<Result<Box<dyn Rows>>>;
+pub type ExecuteReturning = waker::WakableFuture<Result<(ExecuteResult, Box<dyn Rows>)>>;
 pub type Columns = waker::WakableFuture<Result<Vec<ColumnMetaData>>>;
 pub type RowsNext = waker::WakableFuture<Result<bool>>;
 pub type RowsGet = waker::WakableFuture<Result<Value>>;
+pub type RowsGetRow = waker::WakableFuture<Result<Vec<Value>>>;
+pub type RowsNextBatch = waker::WakableFuture<Result<Vec<Vec<Value>>>>;
+pub type Cancel = waker::WakableFuture<Result<()>>;
+
+/// Cancels the operation running on a [`Statement`], may be used from any thread
+pub trait CancelHandle: Send + Sync {
+    /// Interrupt the running execute, query or row fetch of the statement, which
+    /// fails with [`crate::ErrorKind::Cancelled`]. Does nothing if none is running,
+    /// in particular it must not interrupt other statements of the connection.
+    fn cancel(&self) -> Cancel;
+}
 
 pub trait Statement: Send {
     /// Returns the number of placeholder parameters.
@@ -17,9 +32,28 @@ pub trait Statement: Send {
     /// as an INSERT or UPDATE.
     fn execute(&mut self, args: Vec<Arg>) -> Execute;
 
+    /// Executes the statement once for each args of `batch` in a single driver
+    /// call, and returns the result of each execution.
+    ///
+    /// Stops at the first failed execution, whose error context names the batch
+    /// index. The executions before it are not undone.
+    fn execute_batch(&mut self, batch: Vec<Vec<Arg>>) -> ExecuteBatch;
+
     /// executes a query that may return rows, such as a
     /// SELECT.
     fn query(&mut self, args: Vec<Arg>) -> Query;
+
+    /// Executes an INSERT, UPDATE or DELETE with a RETURNING clause, and
+    /// returns its result together with the returned rows.
+    fn execute_returning(&mut self, args: Vec<Arg>) -> ExecuteReturning;
+
+    /// Limit the run time of each execute, query and row fetch, [`None`] disables the limit.
+    ///
+    /// An operation running longer fails with [`crate::ErrorKind::Timeout`].
+    fn set_timeout(&mut self, timeout: Option<Duration>);
+
+    /// Returns a handle cancelling the operation running on this statement.
+    fn cancel_handle(&self) -> Arc<dyn CancelHandle>;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -52,15 +86,32 @@ pub struct Arg {
     pub value: Value,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     I64(i64),
     F64(f64),
     String(String),
     Bytes(Vec<u8>),
     Null,
+    Bool(bool),
+    #[cfg(feature = "chrono")]
+    Date(chrono::NaiveDate),
+    #[cfg(feature = "chrono")]
+    Time(chrono::NaiveTime),
+    #[cfg(feature = "chrono")]
+    DateTime(chrono::NaiveDateTime),
+    /// Timestamp in UTC
+    #[cfg(feature = "chrono")]
+    Timestamp(chrono::DateTime<chrono::Utc>),
+    #[cfg(feature = "decimal")]
+    Decimal(rust_decimal::Decimal),
+    #[cfg(feature = "uuid")]
+    Uuid(uuid::Uuid),
+    #[cfg(feature = "json")]
+    Json(serde_json::Value),
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExecuteResult {
     pub last_insert_id: u64,
     pub raws_affected: u64,
@@ -72,6 +123,16 @@ pub trait Rows: Send {
     fn next(&mut self) -> RowsNext;
 
     fn get(&mut self, pos: Placeholder, column_type: ColumnType) -> RowsGet;
+
+    /// Returns all values of the current row, each fetched with the
+    /// [`ColumnMetaData::column_type`] of its column.
+    fn get_row(&mut self) -> RowsGetRow;
+
+    /// Steps up to `n` rows and returns their values like [`Rows::get_row`],
+    /// fewer than `n` rows means there are no more rows.
+    ///
+    /// The last returned row is the current row.
+    fn next_batch(&mut self, n: usize) -> RowsNextBatch;
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -80,13 +141,30 @@ pub struct ColumnMetaData {
     pub column_name: String,
     pub column_decltype: String,
     pub column_decltype_len: Option<u64>,
+    /// The [`ColumnType`] used to fetch this column's values
+    pub column_type: ColumnType,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ColumnType {
     I64,
     F64,
     String,
     Bytes,
     Null,
+    Bool,
+    #[cfg(feature = "chrono")]
+    Date,
+    #[cfg(feature = "chrono")]
+    Time,
+    #[cfg(feature = "chrono")]
+    DateTime,
+    #[cfg(feature = "chrono")]
+    Timestamp,
+    #[cfg(feature = "decimal")]
+    Decimal,
+    #[cfg(feature = "uuid")]
+    Uuid,
+    #[cfg(feature = "json")]
+    Json,
 }
@@ -0,0 +1,1441 @@
+//! Adapter that turns a blocking driver into an async [`Driver`].
+//!
+//! Implement [`BlockingDriver`] and its connection, transaction, statement and rows traits
+//! with plain [`Result`] returns, then register [`ThreadedDriver::new`] as the driver.
+//! Every call is sent as a [`Task`] to the bounded queue of the worker thread that owns
+//! the resource, so the blocking resources don't need to be `Send`.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, Weak},
+    task::Poll,
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use super::{
+    Arg, Begin, CancelHandle, ColumnMetaData, ColumnType, Columns, Commit, Connection, Connector,
+    Driver, Execute, ExecuteBatch, ExecuteResult, ExecuteReturning, ExecuteScript, Ping,
+    Placeholder, Prepare, Query, Release, Reset, Rollback, RollbackTo, Rows, RowsGet, RowsGetRow,
+    RowsNext, RowsNextBatch, Savepoint, SchemaChanges, Statement, Task, Transaction,
+    TransactionOptions, Value,
+};
+use crate::error::{Error, ErrorKind, Result};
+use crate::waker::{new_shared_waker, Admission, SharedWaker, Waker};
+
+/// Blocking counterpart of [`Driver`]
+pub trait BlockingDriver: Send + 'static {
+    type Connection: BlockingConnection;
+
+    /// Open returns new connection to the database
+    fn open(&mut self, name: &str) -> Result<Self::Connection>;
+}
+
+/// Blocking counterpart of [`Connection`]
+pub trait BlockingConnection {
+    type Statement: BlockingStatement;
+    type Transaction: BlockingTransaction<Statement = Self::Statement>;
+
+    /// Get connection id
+    fn id(&self) -> &str;
+
+    fn prepare(&mut self, query: &str) -> Result<Self::Statement>;
+
+    fn execute_script(&mut self, script: &str) -> Result<ExecuteResult>;
+
+    fn begin(&mut self, options: TransactionOptions) -> Result<Self::Transaction>;
+
+    fn ping(&mut self) -> Result<()>;
+
+    fn reset(&mut self) -> Result<bool>;
+}
+
+/// Blocking counterpart of [`Transaction`]
+pub trait BlockingTransaction {
+    type Statement: BlockingStatement;
+
+    fn prepare(&mut self, query: &str) -> Result<Self::Statement>;
+
+    fn execute_script(&mut self, script: &str) -> Result<ExecuteResult>;
+
+    fn commit(&mut self) -> Result<()>;
+
+    fn rollback(&mut self) -> Result<()>;
+
+    fn savepoint(&mut self, name: &str) -> Result<()>;
+
+    fn release(&mut self, name: &str) -> Result<()>;
+
+    fn rollback_to(&mut self, name: &str) -> Result<()>;
+
+    /// Called once when the transaction begins, the handle is used from other threads.
+    fn schema_changes(&self) -> Arc<dyn SchemaChanges>;
+}
+
+/// Blocking counterpart of [`Statement`]
+pub trait BlockingStatement {
+    type Rows: BlockingRows;
+
+    fn num_input(&self) -> Option<u32>;
+
+    fn execute(&mut self, args: Vec<Arg>) -> Result<ExecuteResult>;
+
+    fn execute_batch(&mut self, batch: Vec<Vec<Arg>>) -> Result<Vec<ExecuteResult>>;
+
+    fn query(&mut self, args: Vec<Arg>) -> Result<Self::Rows>;
+
+    fn execute_returning(&mut self, args: Vec<Arg>) -> Result<(ExecuteResult, Self::Rows)>;
+
+    fn set_timeout(&mut self, timeout: Option<Duration>);
+
+    /// Called once when the statement is prepared, the handle is used from other threads
+    /// while the worker thread is busy with this statement.
+    fn cancel_handle(&self) -> Arc<dyn CancelHandle>;
+}
+
+/// Blocking counterpart of [`Rows`]
+pub trait BlockingRows {
+    fn colunms(&mut self) -> Result<Vec<ColumnMetaData>>;
+
+    fn next(&mut self) -> Result<bool>;
+
+    fn get(&mut self, pos: Placeholder, column_type: ColumnType) -> Result<Value>;
+
+    fn get_row(&mut self) -> Result<Vec<Value>>;
+
+    fn next_batch(&mut self, n: usize) -> Result<Vec<Vec<Value>>>;
+}
+
+type StatementOf<D> = <<D as BlockingDriver>::Connection as BlockingConnection>::Statement;
+type TransactionOf<D> = <<D as BlockingDriver>::Connection as BlockingConnection>::Transaction;
+type RowsOf<D> = <StatementOf<D> as BlockingStatement>::Rows;
+
+/// Options of [`ThreadedDriver::with_options`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadedOptions {
+    /// Number of worker threads, at least one.
+    pub workers: usize,
+    /// Tasks each worker queue holds before new senders wait for capacity, at least one.
+    pub queue_capacity: usize,
+}
+
+impl Default for ThreadedOptions {
+    /// One worker per available CPU, see [`std::thread::available_parallelism`]
+    fn default() -> Self {
+        Self {
+            workers: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            queue_capacity: 1024,
+        }
+    }
+}
+
+/// Task queue depth of a [`ThreadedDriver`], summed over its workers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStatus {
+    /// Tasks in the worker queues, not running yet
+    pub queued: usize,
+    /// Senders parked on a full worker queue, waiting for capacity
+    pub waiting: usize,
+    /// Capacity of all worker queues
+    pub capacity: usize,
+}
+
+/// Entry of the queue behind the full task queue, in send order
+enum Parked {
+    /// Sender waiting for room, with the waker of its future once polled
+    Sender(u64, Option<std::task::Waker>),
+    /// Task without a future, or of a sender dropped while waiting, it runs in its turn
+    Task(Task),
+}
+
+#[derive(Default)]
+struct QueueState {
+    tasks: VecDeque<Task>,
+    parked: VecDeque<Parked>,
+    next_ticket: u64,
+    closed: bool,
+}
+
+impl QueueState {
+    fn is_first(&self, ticket: u64) -> bool {
+        matches!(self.parked.front(), Some(Parked::Sender(first, _)) if *first == ticket)
+    }
+
+    fn parked_sender(&mut self, ticket: u64) -> Option<&mut Parked> {
+        self.parked
+            .iter_mut()
+            .find(|parked| matches!(parked, Parked::Sender(id, _) if *id == ticket))
+    }
+}
+
+/// Bounded task queue of a worker
+///
+/// At most `capacity` tasks are queued. A sender finding the queue full is parked in send
+/// order: its future stays pending, without the task being built, until the worker takes
+/// a task and the sender is first in line, so callers awaiting it are held back without
+/// blocking their executor.
+///
+/// Tasks without a future (closing a resource, setting a timeout) and the tasks of senders
+/// dropped while parked keep their place in line, but aren't held back by the capacity.
+struct TaskQueue {
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    capacity: usize,
+}
+
+impl TaskQueue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            state: Default::default(),
+            not_empty: Condvar::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Queues `task` regardless of the capacity, fails it if the queue is closed.
+    fn push(&self, task: Task) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.closed {
+            drop(state);
+
+            task.fail(Error::msg(ErrorKind::Connection, "driver shut down"));
+
+            return;
+        }
+
+        // a task never overtakes the parked ones, which keeps the order of each connection
+        if state.parked.is_empty() {
+            state.tasks.push_back(task);
+        } else {
+            state.parked.push_back(Parked::Task(task));
+        }
+
+        self.not_empty.notify_one();
+    }
+
+    /// Moves the tasks at the head of the line into the queue, then wakes the first
+    /// parked sender if there is room for it.
+    fn advance(&self, state: &mut QueueState) {
+        while let Some(Parked::Task(_)) = state.parked.front() {
+            if let Some(Parked::Task(task)) = state.parked.pop_front() {
+                state.tasks.push_back(task);
+            }
+        }
+
+        if state.tasks.len() < self.capacity {
+            if let Some(Parked::Sender(_, Some(waker))) = state.parked.front() {
+                waker.wake_by_ref();
+            }
+        }
+
+        self.not_empty.notify_one();
+    }
+
+    /// Takes the next task, returns [`None`] once the queue is closed and empty.
+    fn recv(&self) -> Option<Task> {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(task) = state.tasks.pop_front() {
+                self.advance(&mut state);
+
+                return Some(task);
+            }
+
+            if state.closed {
+                return None;
+            }
+
+            state = self.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Takes all the queued tasks, without blocking.
+    fn drain(&self) -> Vec<Task> {
+        self.state.lock().unwrap().tasks.drain(..).collect()
+    }
+
+    /// Rejects new tasks and fails the parked senders, the queued tasks still run.
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        state.closed = true;
+
+        for parked in std::mem::take(&mut state.parked) {
+            match parked {
+                Parked::Task(task) => state.tasks.push_back(task),
+                Parked::Sender(_, waker) => {
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }
+            }
+        }
+
+        self.not_empty.notify_all();
+    }
+
+    fn status(&self) -> QueueStatus {
+        let state = self.state.lock().unwrap();
+
+        QueueStatus {
+            queued: state.tasks.len(),
+            waiting: state
+                .parked
+                .iter()
+                .filter(|parked| matches!(parked, Parked::Sender(..)))
+                .count(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// Closes the task queue when the driver and every resource handle of the worker are dropped
+struct QueueSender(Arc<TaskQueue>);
+
+impl Drop for QueueSender {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Builds the task of a sender once it is admitted to the queue
+type BuildTask<T> = Box<dyn FnOnce(SharedWaker<Result<T>>) -> Task + Send>;
+
+/// [`Admission`] of a sender parked on a full [`TaskQueue`]
+///
+/// Keeps the queue open, like the resource handle that sent it.
+struct ParkedSender<T> {
+    sender: Arc<QueueSender>,
+    ticket: u64,
+    /// Waker of the sender future, which owns this admission
+    waker: Weak<Mutex<Waker<Result<T>>>>,
+    task: Option<BuildTask<T>>,
+}
+
+impl<T: Send + 'static> Admission<Result<T>> for ParkedSender<T> {
+    fn poll_admit(&mut self, waker: &std::task::Waker) -> Poll<Option<Result<T>>> {
+        let queue = &self.sender.0;
+
+        let mut state = queue.state.lock().unwrap();
+
+        if state.closed {
+            self.task = None;
+
+            return Poll::Ready(Some(Err(Error::msg(
+                ErrorKind::Connection,
+                "driver shut down",
+            ))));
+        }
+
+        if state.is_first(self.ticket) && state.tasks.len() < queue.capacity {
+            // the future being polled holds the waker
+            let (Some(task), Some(result)) = (self.task.take(), self.waker.upgrade()) else {
+                unreachable!("parked sender admitted twice");
+            };
+
+            state.parked.pop_front();
+
+            state.tasks.push_back(task(result));
+
+            queue.advance(&mut state);
+
+            return Poll::Ready(None);
+        }
+
+        if let Some(Parked::Sender(_, parked)) = state.parked_sender(self.ticket) {
+            *parked = Some(waker.clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for ParkedSender<T> {
+    /// Drivers run an operation when it's called, so the task of a future dropped while
+    /// parked still runs in its turn, its result is discarded.
+    fn drop(&mut self) {
+        let Some(task) = self.task.take() else {
+            return;
+        };
+
+        let queue = &self.sender.0;
+
+        let mut state = queue.state.lock().unwrap();
+
+        if let Some(parked) = state.parked_sender(self.ticket) {
+            *parked = Parked::Task(task(new_shared_waker()));
+
+            queue.advance(&mut state);
+        }
+    }
+}
+
+/// Sending side of a worker task queue, shared by the driver and the handles of the worker resources
+#[derive(Clone)]
+struct Queue(Option<Arc<QueueSender>>);
+
+impl Queue {
+    /// Sends the task built by `task` with the waker of its future.
+    ///
+    /// If the queue is full the future waits for room before the task is built, see
+    /// [`TaskQueue`]. Fails the future if the driver has been shut down.
+    fn send<T: Send + 'static>(
+        &self,
+        waker: SharedWaker<Result<T>>,
+        task: impl FnOnce(SharedWaker<Result<T>>) -> Task + Send + 'static,
+    ) {
+        let shut_down = || Err(Error::msg(ErrorKind::Connection, "driver shut down"));
+
+        let Some(sender) = &self.0 else {
+            waker.lock().unwrap().ready(shut_down());
+
+            return;
+        };
+
+        let queue = &sender.0;
+
+        let mut state = queue.state.lock().unwrap();
+
+        if state.closed {
+            drop(state);
+
+            waker.lock().unwrap().ready(shut_down());
+
+            return;
+        }
+
+        if state.parked.is_empty() && state.tasks.len() < queue.capacity {
+            state.tasks.push_back(task(waker));
+
+            queue.not_empty.notify_one();
+
+            return;
+        }
+
+        let ticket = state.next_ticket;
+
+        state.next_ticket += 1;
+
+        state.parked.push_back(Parked::Sender(ticket, None));
+
+        drop(state);
+
+        let admission = ParkedSender {
+            sender: sender.clone(),
+            ticket,
+            waker: Arc::downgrade(&waker),
+            task: Some(Box::new(task)),
+        };
+
+        waker.lock().unwrap().admit_with(admission);
+    }
+
+    /// Sends `task`, which has no future, at once, fails it if the driver has been shut down.
+    fn push(&self, task: Task) {
+        match &self.0 {
+            Some(sender) => sender.0.push(task),
+            None => task.fail(Error::msg(ErrorKind::Connection, "driver shut down")),
+        }
+    }
+}
+
+struct WorkerThread {
+    queue: Queue,
+    tasks: Arc<TaskQueue>,
+    handle: Option<JoinHandle<bool>>,
+}
+
+/// Reports the [`QueueStatus`] of a [`ThreadedDriver`], also after the driver is registered
+#[derive(Clone)]
+pub struct QueueMonitor {
+    queues: Vec<Arc<TaskQueue>>,
+}
+
+impl QueueMonitor {
+    pub fn status(&self) -> QueueStatus {
+        self.queues.iter().map(|queue| queue.status()).fold(
+            QueueStatus::default(),
+            |total, status| QueueStatus {
+                queued: total.queued + status.queued,
+                waiting: total.waiting + status.waiting,
+                capacity: total.capacity + status.capacity,
+            },
+        )
+    }
+}
+
+/// [`Driver`] running a [`BlockingDriver`] on worker threads
+///
+/// Each connection is pinned to the worker thread that opened it, together with its
+/// transactions, statements and rows, so the tasks of one connection run in order
+/// while different connections may run in parallel.
+///
+/// A worker thread exits when the driver and every resource handle of the worker
+/// are dropped, or when the driver is shut down, see [`ThreadedDriver::shutdown`].
+pub struct ThreadedDriver {
+    workers: Vec<WorkerThread>,
+    next_worker: usize,
+}
+
+impl ThreadedDriver {
+    /// Spawns one worker thread that owns `driver` and every resource it opens.
+    pub fn new<D: BlockingDriver>(driver: D) -> Self {
+        Self::spawn(vec![driver], ThreadedOptions::default().queue_capacity)
+    }
+
+    /// Spawns `workers` worker threads (at least one), each owns a clone of `driver`.
+    ///
+    /// New connections are assigned to the workers in turn.
+    pub fn with_workers<D: BlockingDriver + Clone>(driver: D, workers: usize) -> Self {
+        Self::with_options(
+            driver,
+            ThreadedOptions {
+                workers,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Spawns the worker threads configured by `options`, see [`ThreadedDriver::with_workers`].
+    pub fn with_options<D: BlockingDriver + Clone>(driver: D, options: ThreadedOptions) -> Self {
+        Self::spawn(vec![driver; options.workers.max(1)], options.queue_capacity)
+    }
+
+    fn spawn<D: BlockingDriver>(drivers: Vec<D>, queue_capacity: usize) -> Self {
+        let workers = drivers
+            .into_iter()
+            .map(|driver| {
+                let tasks = Arc::new(TaskQueue::new(queue_capacity));
+
+                let sender = Arc::new(QueueSender(tasks.clone()));
+
+                let weak = Arc::downgrade(&sender);
+
+                let receiver = tasks.clone();
+
+                let handle =
+                    std::thread::spawn(move || Worker::new(driver, weak).execute_loop(&receiver));
+
+                WorkerThread {
+                    queue: Queue(Some(sender)),
+                    tasks,
+                    handle: Some(handle),
+                }
+            })
+            .collect();
+
+        Self {
+            workers,
+            next_worker: 0,
+        }
+    }
+
+    /// Number of worker threads
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Current task queue depth
+    pub fn queue_status(&self) -> QueueStatus {
+        self.queue_monitor().status()
+    }
+
+    /// Returns a monitor of the task queues, which can be kept after the driver is registered.
+    pub fn queue_monitor(&self) -> QueueMonitor {
+        QueueMonitor {
+            queues: self
+                .workers
+                .iter()
+                .map(|worker| worker.tasks.clone())
+                .collect(),
+        }
+    }
+
+    /// Closes the task queues, lets the workers run the tasks already queued,
+    /// then closes every connection, transaction, statement and rows and joins the worker threads.
+    ///
+    /// Blocks the calling thread until the workers exit. Later calls of the driver or of
+    /// the resources it opened fail with a "driver shut down" error.
+    pub fn shutdown(&mut self) -> Result<()> {
+        for worker in &self.workers {
+            worker.tasks.close();
+        }
+
+        let mut result = Ok(());
+
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                if !handle.join().unwrap_or(false) {
+                    result = Err(Error::msg(
+                        ErrorKind::Other,
+                        "threaded driver worker panicked while closing its resources",
+                    ));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl Driver for ThreadedDriver {
+    fn open(&mut self, name: &str) -> Connector {
+        let (fut, waker) = Connector::new();
+
+        let worker = self.next_worker;
+
+        self.next_worker = (worker + 1) % self.workers.len();
+
+        let name = name.to_owned();
+
+        self.workers[worker]
+            .queue
+            .send(waker, move |waker| Task::Open(name, waker));
+
+        fut
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        ThreadedDriver::shutdown(self)
+    }
+}
+
+/// Resource registry of the worker thread
+struct Worker<D: BlockingDriver> {
+    driver: D,
+    /// Doesn't keep the queue open, the worker only sends it to new handles
+    queue: Weak<QueueSender>,
+    next_id: u64,
+    cnns: HashMap<String, D::Connection>,
+    stmts: HashMap<String, StatementOf<D>>,
+    txs: HashMap<String, TransactionOf<D>>,
+    results: HashMap<String, RowsOf<D>>,
+    /// Resources dropped because a task panicked using them
+    panicked: HashSet<String>,
+}
+
+fn fetch_object<'a, Obj, Output>(
+    waker: &SharedWaker<Result<Output>>,
+    panicked: &HashSet<String>,
+    map: &'a mut HashMap<String, Obj>,
+    id: &str,
+) -> Option<&'a mut Obj> {
+    let obj = map.get_mut(id);
+
+    if obj.is_none() {
+        let err = if panicked.contains(id) {
+            Error::msg(
+                ErrorKind::Connection,
+                format!("threaded driver worker panicked using resource {}", id),
+            )
+        } else {
+            Error::msg(
+                ErrorKind::Other,
+                format!("threaded driver resource not found {}", id),
+            )
+        };
+
+        waker.lock().unwrap().ready(Err(err));
+    }
+
+    obj
+}
+
+/// Worker registry entry a task runs on
+enum Resource {
+    Connection(String),
+    Transaction(String),
+    Statement(String),
+    Rows(String),
+}
+
+impl Resource {
+    /// The resource used by `task`, [`None`] for [`Task::Open`]
+    fn of(task: &Task) -> Option<Self> {
+        let resource = match task {
+            Task::Open(..) => return None,
+            Task::Begin(id, ..)
+            | Task::Prepare(id, ..)
+            | Task::ExecuteScript(id, ..)
+            | Task::Ping(id, ..)
+            | Task::Reset(id, ..)
+            | Task::CloseConnection(id) => Resource::Connection(id.clone()),
+            Task::TxPrepare(id, ..)
+            | Task::TxExecuteScript(id, ..)
+            | Task::Commit(id, ..)
+            | Task::Rollback(id, ..)
+            | Task::Savepoint(id, ..)
+            | Task::Release(id, ..)
+            | Task::RollbackTo(id, ..)
+            | Task::CloseTx(id) => Resource::Transaction(id.clone()),
+            Task::Execute(id, ..)
+            | Task::ExecuteBatch(id, ..)
+            | Task::SetTimeout(id, ..)
+            | Task::Query(id, ..)
+            | Task::ExecuteReturning(id, ..)
+            | Task::CloseStmt(id) => Resource::Statement(id.clone()),
+            Task::Columns(id, ..)
+            | Task::RowsNext(id, ..)
+            | Task::RowsGet(id, ..)
+            | Task::RowsGetRow(id, ..)
+            | Task::RowsNextBatch(id, ..)
+            | Task::CloseRows(id) => Resource::Rows(id.clone()),
+        };
+
+        Some(resource)
+    }
+}
+
+/// Drops `resource`, returns false if it panicked.
+fn drop_resource<R>(resource: R) -> bool {
+    catch_unwind(AssertUnwindSafe(move || drop(resource))).is_ok()
+}
+
+/// Closes the worker queue and fails the tasks left in it when the worker exits,
+/// also if it panics, so that no caller waits forever.
+struct ExitGuard<'a>(&'a TaskQueue);
+
+impl<'a> Drop for ExitGuard<'a> {
+    fn drop(&mut self) {
+        self.0.close();
+
+        for task in self.0.drain() {
+            task.fail(Error::msg(
+                ErrorKind::Connection,
+                "threaded driver worker exited",
+            ));
+        }
+    }
+}
+
+impl<D: BlockingDriver> Worker<D> {
+    fn new(driver: D, queue: Weak<QueueSender>) -> Self {
+        Self {
+            driver,
+            queue,
+            next_id: 0,
+            cnns: HashMap::new(),
+            stmts: HashMap::new(),
+            txs: HashMap::new(),
+            results: HashMap::new(),
+            panicked: HashSet::new(),
+        }
+    }
+
+    /// Queue of a new resource handle, closed if all other handles are gone
+    fn queue(&self) -> Queue {
+        Queue(self.queue.upgrade())
+    }
+
+    fn new_id(&mut self) -> String {
+        self.next_id += 1;
+
+        self.next_id.to_string()
+    }
+
+    fn insert_statement(&mut self, stmt: StatementOf<D>) -> Box<dyn Statement> {
+        let id = self.new_id();
+
+        let handle = ThreadedStatement {
+            queue: self.queue(),
+            id: id.clone(),
+            inputs: stmt.num_input(),
+            cancel: stmt.cancel_handle(),
+        };
+
+        self.stmts.insert(id, stmt);
+
+        handle.into()
+    }
+
+    fn insert_rows(&mut self, rows: RowsOf<D>) -> Box<dyn Rows> {
+        let id = self.new_id();
+
+        self.results.insert(id.clone(), rows);
+
+        ThreadedRows {
+            queue: self.queue(),
+            id,
+        }
+        .into()
+    }
+
+    /// Runs the tasks until the queue is closed and empty, then closes the resources.
+    ///
+    /// Returns false if closing a resource panicked.
+    fn execute_loop(mut self, receiver: &TaskQueue) -> bool {
+        let _guard = ExitGuard(receiver);
+
+        while let Some(task) = receiver.recv() {
+            let failer = task.failer();
+
+            let resource = Resource::of(&task);
+
+            if let Err(panic) = catch_unwind(AssertUnwindSafe(|| self.execute(task))) {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+
+                log::error!("threaded driver worker panicked: {}", message);
+
+                failer(Error::msg(
+                    ErrorKind::Connection,
+                    format!("threaded driver worker panicked: {}", message),
+                ));
+
+                // The panic may leave the resource broken, the other ones are not touched.
+                if let Some(resource) = resource {
+                    self.drop_panicked(resource);
+                }
+            }
+        }
+
+        self.close_all()
+    }
+
+    /// Drops the resource a panicking task used, later tasks on it fail, see [`fetch_object`]
+    fn drop_panicked(&mut self, resource: Resource) {
+        let (id, dropped) = match resource {
+            Resource::Connection(id) => {
+                let dropped = drop_resource(self.cnns.remove(&id));
+                (id, dropped)
+            }
+            Resource::Transaction(id) => {
+                let dropped = drop_resource(self.txs.remove(&id));
+                (id, dropped)
+            }
+            Resource::Statement(id) => {
+                let dropped = drop_resource(self.stmts.remove(&id));
+                (id, dropped)
+            }
+            Resource::Rows(id) => {
+                let dropped = drop_resource(self.results.remove(&id));
+                (id, dropped)
+            }
+        };
+
+        if !dropped {
+            log::error!("threaded driver resource {} panicked when dropped", id);
+        }
+
+        self.panicked.insert(id);
+    }
+
+    /// Closes the resources, dependent ones first. Returns false if one of them panicked.
+    fn close_all(&mut self) -> bool {
+        let mut closed = true;
+
+        for (_, rows) in self.results.drain() {
+            closed &= drop_resource(rows);
+        }
+
+        for (_, stmt) in self.stmts.drain() {
+            closed &= drop_resource(stmt);
+        }
+
+        for (_, tx) in self.txs.drain() {
+            closed &= drop_resource(tx);
+        }
+
+        for (_, conn) in self.cnns.drain() {
+            closed &= drop_resource(conn);
+        }
+
+        closed
+    }
+
+    fn execute(&mut self, task: Task) {
+        match task {
+            Task::Open(url, waker) => {
+                let result = self.driver.open(&url).map(|conn| {
+                    let id = self.new_id();
+
+                    let handle = ThreadedConnection {
+                        queue: self.queue(),
+                        id: conn.id().to_owned(),
+                        key: id.clone(),
+                    };
+
+                    self.cnns.insert(id, conn);
+
+                    handle.into()
+                });
+
+                waker.lock().unwrap().ready(result);
+            }
+
+            Task::Begin(id, options, waker) => {
+                if let Some(conn) = fetch_object(&waker, &self.panicked, &mut self.cnns, &id) {
+                    let result = conn.begin(options);
+
+                    let result = result.map(|tx| {
+                        let id = self.new_id();
+
+                        let schema_changes = tx.schema_changes();
+
+                        self.txs.insert(id.clone(), tx);
+
+                        ThreadedTransaction {
+                            queue: self.queue(),
+                            id,
+                            schema_changes,
+                        }
+                        .into()
+                    });
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Prepare(id, query, waker) => {
+                if let Some(conn) = fetch_object(&waker, &self.panicked, &mut self.cnns, &id) {
+                    let result = conn.prepare(&query);
+
+                    let result = result.map(|stmt| self.insert_statement(stmt));
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::ExecuteScript(id, script, waker) => {
+                if let Some(conn) = fetch_object(&waker, &self.panicked, &mut self.cnns, &id) {
+                    let result = conn.execute_script(&script);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Ping(id, waker) => {
+                if let Some(conn) = fetch_object(&waker, &self.panicked, &mut self.cnns, &id) {
+                    let result = conn.ping();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Reset(id, waker) => {
+                if let Some(conn) = fetch_object(&waker, &self.panicked, &mut self.cnns, &id) {
+                    let result = conn.reset();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Execute(id, args, waker) => {
+                if let Some(stmt) = fetch_object(&waker, &self.panicked, &mut self.stmts, &id) {
+                    let result = stmt.execute(args);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::ExecuteBatch(id, batch, waker) => {
+                if let Some(stmt) = fetch_object(&waker, &self.panicked, &mut self.stmts, &id) {
+                    let result = stmt.execute_batch(batch);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::SetTimeout(id, timeout) => {
+                if let Some(stmt) = self.stmts.get_mut(&id) {
+                    stmt.set_timeout(timeout);
+                }
+            }
+
+            Task::Query(id, args, waker) => {
+                if let Some(stmt) = fetch_object(&waker, &self.panicked, &mut self.stmts, &id) {
+                    let result = stmt.query(args);
+
+                    let result = result.map(|rows| self.insert_rows(rows));
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::ExecuteReturning(id, args, waker) => {
+                if let Some(stmt) = fetch_object(&waker, &self.panicked, &mut self.stmts, &id) {
+                    let result = stmt.execute_returning(args);
+
+                    let result = result.map(|(result, rows)| (result, self.insert_rows(rows)));
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Columns(id, waker) => {
+                if let Some(rows) = fetch_object(&waker, &self.panicked, &mut self.results, &id) {
+                    let result = rows.colunms();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::RowsNext(id, waker) => {
+                if let Some(rows) = fetch_object(&waker, &self.panicked, &mut self.results, &id) {
+                    let result = rows.next();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::RowsGet(id, pos, column_type, waker) => {
+                if let Some(rows) = fetch_object(&waker, &self.panicked, &mut self.results, &id) {
+                    let result = rows.get(pos, column_type);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::RowsGetRow(id, waker) => {
+                if let Some(rows) = fetch_object(&waker, &self.panicked, &mut self.results, &id) {
+                    let result = rows.get_row();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::RowsNextBatch(id, n, waker) => {
+                if let Some(rows) = fetch_object(&waker, &self.panicked, &mut self.results, &id) {
+                    let result = rows.next_batch(n);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::TxPrepare(id, query, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.prepare(&query);
+
+                    let result = result.map(|stmt| self.insert_statement(stmt));
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::TxExecuteScript(id, script, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.execute_script(&script);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Commit(id, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.commit();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Rollback(id, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.rollback();
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Savepoint(id, name, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.savepoint(&name);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::Release(id, name, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.release(&name);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::RollbackTo(id, name, waker) => {
+                if let Some(tx) = fetch_object(&waker, &self.panicked, &mut self.txs, &id) {
+                    let result = tx.rollback_to(&name);
+
+                    waker.lock().unwrap().ready(result);
+                }
+            }
+
+            Task::CloseConnection(id) => {
+                self.panicked.remove(&id);
+                self.cnns.remove(&id);
+            }
+
+            Task::CloseTx(id) => {
+                self.panicked.remove(&id);
+                self.txs.remove(&id);
+            }
+
+            Task::CloseStmt(id) => {
+                self.panicked.remove(&id);
+                self.stmts.remove(&id);
+            }
+
+            Task::CloseRows(id) => {
+                self.panicked.remove(&id);
+                self.results.remove(&id);
+            }
+        }
+    }
+}
+
+struct ThreadedConnection {
+    queue: Queue,
+    /// Id reported by the blocking connection
+    id: String,
+    /// Registry key of the blocking connection
+    key: String,
+}
+
+impl From<ThreadedConnection> for Box<dyn Connection> {
+    fn from(val: ThreadedConnection) -> Self {
+        Box::new(val)
+    }
+}
+
+impl Drop for ThreadedConnection {
+    fn drop(&mut self) {
+        self.queue.push(Task::CloseConnection(self.key.clone()));
+    }
+}
+
+impl Connection for ThreadedConnection {
+    fn begin(&mut self, options: TransactionOptions) -> Begin {
+        let (fut, waker) = Begin::new();
+
+        let id = self.key.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::Begin(id, options, waker));
+
+        fut
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_valid(&self) -> bool {
+        true
+    }
+
+    fn ping(&mut self) -> Ping {
+        let (fut, waker) = Ping::new();
+
+        let id = self.key.clone();
+
+        self.queue.send(waker, move |waker| Task::Ping(id, waker));
+
+        fut
+    }
+
+    fn reset(&mut self) -> Reset {
+        let (fut, waker) = Reset::new();
+
+        let id = self.key.clone();
+
+        self.queue.send(waker, move |waker| Task::Reset(id, waker));
+
+        fut
+    }
+
+    fn prepare(&mut self, query: &str) -> Prepare {
+        let (fut, waker) = Prepare::new();
+
+        let id = self.key.clone();
+
+        let query = query.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::Prepare(id, query, waker));
+
+        fut
+    }
+
+    fn execute_script(&mut self, script: &str) -> ExecuteScript {
+        let (fut, waker) = ExecuteScript::new();
+
+        let id = self.key.clone();
+
+        let script = script.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::ExecuteScript(id, script, waker));
+
+        fut
+    }
+}
+
+struct ThreadedTransaction {
+    queue: Queue,
+    id: String,
+    /// Read on the caller thread directly, like the cancel handle of a statement
+    schema_changes: Arc<dyn SchemaChanges>,
+}
+
+impl From<ThreadedTransaction> for Box<dyn Transaction> {
+    fn from(val: ThreadedTransaction) -> Self {
+        Box::new(val)
+    }
+}
+
+impl Drop for ThreadedTransaction {
+    fn drop(&mut self) {
+        self.queue.push(Task::CloseTx(self.id.clone()));
+    }
+}
+
+impl Transaction for ThreadedTransaction {
+    fn commit(&mut self) -> Commit {
+        let (fut, waker) = Commit::new();
+
+        let id = self.id.clone();
+
+        self.queue.send(waker, move |waker| Task::Commit(id, waker));
+
+        fut
+    }
+
+    fn prepare(&mut self, query: &str) -> Prepare {
+        let (fut, waker) = Prepare::new();
+
+        let id = self.id.clone();
+
+        let query = query.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::TxPrepare(id, query, waker));
+
+        fut
+    }
+
+    fn execute_script(&mut self, script: &str) -> ExecuteScript {
+        let (fut, waker) = ExecuteScript::new();
+
+        let id = self.id.clone();
+
+        let script = script.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::TxExecuteScript(id, script, waker));
+
+        fut
+    }
+
+    fn rollback(&mut self) -> Rollback {
+        let (fut, waker) = Rollback::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::Rollback(id, waker));
+
+        fut
+    }
+
+    fn savepoint(&mut self, name: &str) -> Savepoint {
+        let (fut, waker) = Savepoint::new();
+
+        let id = self.id.clone();
+
+        let name = name.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::Savepoint(id, name, waker));
+
+        fut
+    }
+
+    fn release(&mut self, name: &str) -> Release {
+        let (fut, waker) = Release::new();
+
+        let id = self.id.clone();
+
+        let name = name.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::Release(id, name, waker));
+
+        fut
+    }
+
+    fn rollback_to(&mut self, name: &str) -> RollbackTo {
+        let (fut, waker) = RollbackTo::new();
+
+        let id = self.id.clone();
+
+        let name = name.to_owned();
+
+        self.queue
+            .send(waker, move |waker| Task::RollbackTo(id, name, waker));
+
+        fut
+    }
+
+    fn schema_changes(&self) -> Arc<dyn SchemaChanges> {
+        self.schema_changes.clone()
+    }
+}
+
+struct ThreadedStatement {
+    queue: Queue,
+    id: String,
+    inputs: Option<u32>,
+    /// Cancels on the caller thread directly, a task would wait for the running one
+    cancel: Arc<dyn CancelHandle>,
+}
+
+impl From<ThreadedStatement> for Box<dyn Statement> {
+    fn from(val: ThreadedStatement) -> Self {
+        Box::new(val)
+    }
+}
+
+impl Drop for ThreadedStatement {
+    fn drop(&mut self) {
+        self.queue.push(Task::CloseStmt(self.id.clone()));
+    }
+}
+
+impl Statement for ThreadedStatement {
+    fn execute(&mut self, args: Vec<Arg>) -> Execute {
+        let (fut, waker) = Execute::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::Execute(id, args, waker));
+
+        fut
+    }
+
+    fn execute_batch(&mut self, batch: Vec<Vec<Arg>>) -> ExecuteBatch {
+        let (fut, waker) = ExecuteBatch::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::ExecuteBatch(id, batch, waker));
+
+        fut
+    }
+
+    fn num_input(&self) -> Option<u32> {
+        self.inputs
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.queue.push(Task::SetTimeout(self.id.clone(), timeout));
+    }
+
+    fn cancel_handle(&self) -> Arc<dyn CancelHandle> {
+        self.cancel.clone()
+    }
+
+    fn execute_returning(&mut self, args: Vec<Arg>) -> ExecuteReturning {
+        let (fut, waker) = ExecuteReturning::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::ExecuteReturning(id, args, waker));
+
+        fut
+    }
+
+    fn query(&mut self, args: Vec<Arg>) -> Query {
+        let (fut, waker) = Query::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::Query(id, args, waker));
+
+        fut
+    }
+}
+
+struct ThreadedRows {
+    queue: Queue,
+    id: String,
+}
+
+impl From<ThreadedRows> for Box<dyn Rows> {
+    fn from(val: ThreadedRows) -> Self {
+        Box::new(val)
+    }
+}
+
+impl Drop for ThreadedRows {
+    fn drop(&mut self) {
+        self.queue.push(Task::CloseRows(self.id.clone()));
+    }
+}
+
+impl Rows for ThreadedRows {
+    fn colunms(&mut self) -> Columns {
+        let (fut, waker) = Columns::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::Columns(id, waker));
+
+        fut
+    }
+
+    fn get(&mut self, pos: Placeholder, column_type: ColumnType) -> RowsGet {
+        let (fut, waker) = RowsGet::new();
+
+        let id = self.id.clone();
+
+        self.queue.send(waker, move |waker| {
+            Task::RowsGet(id, pos, column_type, waker)
+        });
+
+        fut
+    }
+
+    fn next(&mut self) -> RowsNext {
+        let (fut, waker) = RowsNext::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::RowsNext(id, waker));
+
+        fut
+    }
+
+    fn get_row(&mut self) -> RowsGetRow {
+        let (fut, waker) = RowsGetRow::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::RowsGetRow(id, waker));
+
+        fut
+    }
+
+    fn next_batch(&mut self, n: usize) -> RowsNextBatch {
+        let (fut, waker) = RowsNextBatch::new();
+
+        let id = self.id.clone();
+
+        self.queue
+            .send(waker, move |waker| Task::RowsNextBatch(id, n, waker));
+
+        fut
+    }
+}
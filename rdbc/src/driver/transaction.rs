@@ -1,9 +1,60 @@
+use std::sync::Arc;
+
+use super::ExecuteScript;
 use super::Prepare;
+use crate::error::Result;
 use crate::waker;
-use anyhow::*;
 
 pub type Rollback = waker::WakableFuture<Result<()>>;
 pub type Commit = waker::WakableFuture<Result<()>>;
+pub type Savepoint = waker::WakableFuture<Result<()>>;
+pub type Release = waker::WakableFuture<Result<()>>;
+pub type RollbackTo = waker::WakableFuture<Result<()>>;
+
+/// Portable transaction isolation level.
+///
+/// Drivers map it to the closest level they support that is at least as strict,
+/// e.g. sqlite3 transactions are always serializable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+/// When a transaction acquires its locks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LockingMode {
+    /// Locks are acquired on first read/write, a reader may fail to upgrade to writer.
+    #[default]
+    Deferred,
+    /// The write lock is acquired when the transaction begins, other connections can still read.
+    Immediate,
+    /// The exclusive lock is acquired when the transaction begins, other connections can't read or write.
+    Exclusive,
+}
+
+/// Options of [`super::Connection::begin`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionOptions {
+    /// [`None`] uses the driver default isolation level.
+    pub isolation_level: Option<IsolationLevel>,
+    /// Reject writes within the transaction.
+    pub read_only: bool,
+    pub locking_mode: LockingMode,
+}
+
+/// Reports the schema changes of a [`Transaction`], may be used from any thread
+pub trait SchemaChanges: Send + Sync {
+    /// Returns true if a statement run since the transaction began created, altered or
+    /// dropped a schema object, so statements prepared before a rollback may be stale.
+    ///
+    /// Drivers that can't tell return true.
+    fn changed(&self) -> bool;
+}
 
 /// Driver transaction trait .
 ///
@@ -12,7 +63,30 @@ pub type Commit = waker::WakableFuture<Result<()>>;
 pub trait Transaction: Send {
     fn prepare(&mut self, query: &str) -> Prepare;
 
+    /// Runs every statement of `script` within this transaction,
+    /// see [`super::Connection::execute_script`].
+    fn execute_script(&mut self, script: &str) -> ExecuteScript;
+
     fn commit(&mut self) -> Commit;
 
     fn rollback(&mut self) -> Rollback;
+
+    /// Create a savepoint named `name` within this transaction
+    fn savepoint(&mut self, name: &str) -> Savepoint;
+
+    /// Release savepoint `name` and all savepoints created after it,
+    /// their changes become part of the enclosing transaction.
+    ///
+    /// The operation must be submitted when this method is called, not when the future
+    /// is first polled: a dropped nested transaction calls it and drops the future.
+    fn release(&mut self, name: &str) -> Release;
+
+    /// Undo the changes made after savepoint `name` was created,
+    /// the savepoint itself stays active.
+    ///
+    /// Submitted when called, like [`Transaction::release`].
+    fn rollback_to(&mut self, name: &str) -> RollbackTo;
+
+    /// Returns a handle reporting the schema changes of this transaction.
+    fn schema_changes(&self) -> Arc<dyn SchemaChanges>;
 }
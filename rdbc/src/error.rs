@@ -0,0 +1,169 @@
+use std::fmt::{Debug, Display};
+
+use super::pool::PoolError;
+use super::rows::RowsError;
+use super::value::ValueError;
+
+/// Portable category of an [`Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Unique, primary key, foreign key, not null or check constraint violated
+    Constraint,
+    /// The database is busy or locked by another connection
+    Busy,
+    /// The transaction conflicts with a concurrent transaction and can't be serialized
+    Serialization,
+    /// The query returned no rows
+    NotFound,
+    /// SQL syntax error, or unknown table or column
+    Syntax,
+    /// Can't open the database or the connection is lost
+    Connection,
+    /// The value can't be converted to or from the requested type
+    TypeMismatch,
+    /// The operation timed out
+    Timeout,
+    /// The operation has been cancelled
+    Cancelled,
+    /// Other errors
+    Other,
+}
+
+/// rdbc error, a portable [`ErrorKind`] with the driver specific detail
+pub struct Error {
+    kind: ErrorKind,
+    detail: anyhow::Error,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Create error of `kind` with driver specific `detail`
+    pub fn new<E>(kind: ErrorKind, detail: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self {
+            kind,
+            detail: anyhow::Error::new(detail),
+        }
+    }
+
+    /// Create error of `kind` from a message
+    pub fn msg<M>(kind: ErrorKind, message: M) -> Self
+    where
+        M: Display + Debug + Send + Sync + 'static,
+    {
+        Self {
+            kind,
+            detail: anyhow::Error::msg(message),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Wrap the detail with a higher level `context` message, keeping the kind
+    pub fn context<C>(self, context: C) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        Self {
+            kind: self.kind,
+            detail: self.detail.context(context),
+        }
+    }
+
+    /// Returns true if retrying the transaction may succeed, see [`crate::RetryPolicy`]
+    pub fn is_transient(&self) -> bool {
+        matches!(self.kind, ErrorKind::Busy | ErrorKind::Serialization)
+    }
+
+    /// The driver specific error detail
+    pub fn detail(&self) -> &anyhow::Error {
+        &self.detail
+    }
+
+    pub fn into_detail(self) -> anyhow::Error {
+        self.detail
+    }
+
+    /// Returns true if the detail is of type `E`
+    pub fn is<E>(&self) -> bool
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        self.detail.is::<E>()
+    }
+
+    /// Downcast the detail to type `E`
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        self.detail.downcast_ref::<E>()
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {:?}", self.kind, self.detail)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.detail, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // `Display` already prints the detail, its sources follow
+        self.detail.source()
+    }
+}
+
+/// Unwraps an [`Error`] which has been converted to [`anyhow::Error`],
+/// other errors are of [`ErrorKind::Other`].
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<Error>() {
+            Ok(err) => err,
+            Err(detail) => Self {
+                kind: ErrorKind::Other,
+                detail,
+            },
+        }
+    }
+}
+
+impl From<PoolError> for Error {
+    fn from(err: PoolError) -> Self {
+        let kind = match err {
+            PoolError::AcquireTimeout(_) => ErrorKind::Timeout,
+            PoolError::DriverNotFound(_) => ErrorKind::Connection,
+        };
+
+        Error::new(kind, err)
+    }
+}
+
+impl From<RowsError> for Error {
+    fn from(err: RowsError) -> Self {
+        let kind = match err {
+            RowsError::NoRows => ErrorKind::NotFound,
+            RowsError::UnknownColumn(_) | RowsError::OutOfRange(_) => ErrorKind::Other,
+        };
+
+        Error::new(kind, err)
+    }
+}
+
+impl From<ValueError> for Error {
+    fn from(err: ValueError) -> Self {
+        Error::new(ErrorKind::TypeMismatch, err)
+    }
+}
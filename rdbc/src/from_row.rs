@@ -0,0 +1,15 @@
+use super::error::Result;
+use super::rows::Row;
+
+/// Conversion from a result [`Row`], used by [`crate::Rows::fetch_all`] and friends
+///
+/// Usually implemented with `#[derive(FromRow)]` from `rdbc-attributes`.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+impl FromRow for Row {
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(row.clone())
+    }
+}
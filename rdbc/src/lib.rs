@@ -1,32 +1,147 @@
+mod cache;
 mod database;
 mod datasource;
 pub mod driver;
+mod error;
+mod from_row;
+mod params;
+mod pool;
 mod rows;
 mod statement;
+mod timer;
 mod transaction;
+mod value;
 mod waker;
 
 pub use database::*;
 pub use datasource::*;
+pub use error::{Error, ErrorKind, Result};
+pub use from_row::*;
+pub use params::*;
+pub use pool::{PoolError, PoolOptions, PoolStatus, StatementCacheStatus, Validation};
 pub use rows::*;
 pub use statement::*;
 pub use transaction::*;
+pub use value::*;
 pub use waker::*;
 
-pub use driver::{Arg, ColumnMetaData, ColumnType, ExecuteResult, Placeholder, Value};
+pub use anyhow;
+
+pub use driver::{
+    Arg, ColumnMetaData, ColumnType, ExecuteResult, IsolationLevel, LockingMode, Placeholder,
+    TransactionOptions, Value,
+};
+
+/// Compile-time checks that the futures and types returned by rdbc are `Send`,
+/// so they can be spawned on multi-threaded executors.
+#[allow(dead_code, clippy::let_underscore_future)]
+mod assert_send {
+    use super::*;
+
+    fn is_send<T: Send>(_: &T) {}
+
+    fn is_send_type<T: Send>() {}
+
+    fn driver_futures() {
+        is_send_type::<driver::Connector>();
+        is_send_type::<driver::Prepare>();
+        is_send_type::<driver::Begin>();
+        is_send_type::<driver::Ping>();
+        is_send_type::<driver::Reset>();
+        is_send_type::<driver::ExecuteScript>();
+        is_send_type::<driver::Execute>();
+        is_send_type::<driver::ExecuteBatch>();
+        is_send_type::<driver::Query>();
+        is_send_type::<driver::ExecuteReturning>();
+        is_send_type::<driver::Columns>();
+        is_send_type::<driver::RowsNext>();
+        is_send_type::<driver::RowsGet>();
+        is_send_type::<driver::RowsGetRow>();
+        is_send_type::<driver::RowsNextBatch>();
+        is_send_type::<driver::Commit>();
+        is_send_type::<driver::Rollback>();
+        is_send_type::<driver::Savepoint>();
+        is_send_type::<driver::Release>();
+        is_send_type::<driver::RollbackTo>();
+        is_send_type::<driver::Cancel>();
+        is_send_type::<WakableMapFuture<Result<Rows<'static>>, Result<Box<dyn driver::Rows>>>>();
+    }
+
+    fn types() {
+        is_send_type::<Database>();
+        is_send_type::<Transaction>();
+        is_send_type::<NestedTransaction<'static>>();
+        is_send_type::<Statement<'static>>();
+        is_send_type::<Rows<'static>>();
+        is_send_type::<Row>();
+        is_send_type::<CancelHandle>();
+        is_send_type::<Error>();
+    }
+
+    fn database(db: &mut Database) {
+        is_send(&db.prepare(""));
+        is_send(&db.begin());
+        is_send(&db.execute_script(""));
+        is_send(&db.execute_script_in_transaction(""));
+        is_send(&db.begin_with(Default::default()));
+        is_send(&db.transaction(|_| Box::pin(async { Ok(()) })));
+    }
+
+    fn transaction(tx: &mut Transaction) {
+        is_send(&tx.prepare(""));
+        is_send(&tx.execute_script(""));
+        is_send(&tx.commit());
+        is_send(&tx.rollback());
+        is_send(&tx.savepoint(""));
+        is_send(&tx.release(""));
+        is_send(&tx.rollback_to(""));
+        is_send(&tx.begin_nested());
+    }
+
+    fn nested_transaction(tx: &mut NestedTransaction) {
+        is_send(&tx.prepare(""));
+        is_send(&tx.commit());
+        is_send(&tx.rollback());
+        is_send(&tx.begin_nested());
+    }
+
+    fn statement(stmt: &mut Statement) {
+        is_send(&stmt.execute(params![1, "hello"]));
+        is_send(&stmt.query(named_params! {":id" => 1}));
+        is_send(&stmt.execute_returning(params![1]));
+        is_send(&stmt.execute_batch([params![1], params![2]]));
+        is_send(&stmt.execute_stream(futures::stream::iter([[1], [2]])));
+    }
+
+    fn cancel(handle: &CancelHandle) {
+        is_send(&handle.cancel());
+    }
+
+    fn rows(rows: &mut Rows) {
+        is_send(&rows.colunms());
+        is_send(&rows.next());
+        is_send(&rows.get(0, ColumnType::I64));
+        is_send(&rows.get_row());
+    }
+
+    fn fetch(rows: Rows) {
+        is_send(&rows.fetch_all::<Row>());
+    }
+}
 
 #[cfg(feature = "global-datasource")]
 mod global {
     use super::*;
-    use anyhow::*;
 
     fn global_datasource() -> &'static mut DataSource {
         static mut CONF: std::mem::MaybeUninit<DataSource> = std::mem::MaybeUninit::uninit();
         static ONCE: std::sync::Once = std::sync::Once::new();
         ONCE.call_once(|| unsafe {
-            CONF.as_mut_ptr().write(DataSource::new());
+            std::ptr::addr_of_mut!(CONF)
+                .cast::<DataSource>()
+                .write(DataSource::new());
         });
-        unsafe { &mut *CONF.as_mut_ptr() }
+        unsafe { &mut *std::ptr::addr_of_mut!(CONF).cast::<DataSource>() }
     }
 
     pub fn register_driver(name: &str, driver: impl driver::Driver + 'static) -> Result<()> {
@@ -40,6 +155,10 @@ mod global {
     pub fn open(name: &str, url: &str) -> Result<Database> {
         global_datasource().open(name, url)
     }
+
+    pub fn open_with(name: &str, url: &str, options: PoolOptions) -> Result<Database> {
+        global_datasource().open_with(name, url, options)
+    }
 }
 
 #[cfg(feature = "global-datasource")]
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use super::driver::{Arg, Placeholder};
+use super::error::Result;
+use super::value::ToValue;
+
+/// Statement parameters accepted by [`crate::Statement::execute`] and [`crate::Statement::query`]
+///
+/// Positional parameters get 1-based indices, use [`params!`](crate::params)
+/// and [`named_params!`](crate::named_params) to mix value types.
+pub trait Params {
+    fn into_args(self) -> Result<Vec<Arg>>;
+}
+
+impl Params for Vec<Arg> {
+    fn into_args(self) -> Result<Vec<Arg>> {
+        Ok(self)
+    }
+}
+
+impl Params for () {
+    fn into_args(self) -> Result<Vec<Arg>> {
+        Ok(vec![])
+    }
+}
+
+/// Positional parameters
+impl<T, const N: usize> Params for [T; N]
+where
+    T: ToValue,
+{
+    fn into_args(self) -> Result<Vec<Arg>> {
+        self.iter()
+            .enumerate()
+            .map(|(index, value)| {
+                Ok(Arg {
+                    pos: Placeholder::Index(index as u64 + 1),
+                    value: value.to_value()?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Named parameters, the names include the prefix, e.g. `":name"`
+impl<T, const N: usize> Params for [(&str, T); N]
+where
+    T: ToValue,
+{
+    fn into_args(self) -> Result<Vec<Arg>> {
+        self.iter()
+            .map(|(name, value)| {
+                Ok(Arg {
+                    pos: Placeholder::Name(name.to_string()),
+                    value: value.to_value()?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Named parameters, the names include the prefix, e.g. `":name"`
+impl<T> Params for HashMap<String, T>
+where
+    T: ToValue,
+{
+    fn into_args(self) -> Result<Vec<Arg>> {
+        self.into_iter()
+            .map(|(name, value)| {
+                Ok(Arg {
+                    pos: Placeholder::Name(name),
+                    value: value.to_value()?,
+                })
+            })
+            .collect()
+    }
+}
+
+macro_rules! declare_tuple_params {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name),+> Params for ($($name,)+)
+        where
+            $($name: ToValue,)+
+        {
+            fn into_args(self) -> Result<Vec<Arg>> {
+                Ok(vec![
+                    $(Arg {
+                        pos: Placeholder::Index($index + 1),
+                        value: self.$index.to_value()?,
+                    },)+
+                ])
+            }
+        }
+    };
+}
+
+declare_tuple_params!(A 0);
+declare_tuple_params!(A 0, B 1);
+declare_tuple_params!(A 0, B 1, C 2);
+declare_tuple_params!(A 0, B 1, C 2, D 3);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+declare_tuple_params!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// Positional parameters of mixed types, bound to indices `1..`
+///
+/// ```ignore
+/// stmt.execute(rdbc::params![1, "hello", None::<f64>]).await?;
+/// ```
+#[macro_export]
+macro_rules! params {
+    () => {
+        [] as [&dyn $crate::ToValue; 0]
+    };
+    ($($value:expr),+ $(,)?) => {
+        [$(&$value as &dyn $crate::ToValue),+]
+    };
+}
+
+/// Named parameters of mixed types
+///
+/// ```ignore
+/// stmt.execute(rdbc::named_params! { ":name" => name, ":age" => 18 }).await?;
+/// ```
+#[macro_export]
+macro_rules! named_params {
+    () => {
+        [] as [(&str, &dyn $crate::ToValue); 0]
+    };
+    ($($name:expr => $value:expr),+ $(,)?) => {
+        [$(($name, &$value as &dyn $crate::ToValue)),+]
+    };
+}
@@ -0,0 +1,701 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use super::cache::{CacheCounters, StatementCache};
+use super::driver;
+use super::error::{Error, Result};
+use super::timer;
+use super::waker;
+use thiserror::Error;
+
+/// Retry interval of the pool maintenance after failing to open a `min_idle_connections` connection.
+const MAINTAIN_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Connection pool configuration, used by [`crate::DataSource::open_with`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolOptions {
+    /// The maximum number of connections opened by the pool at the same time.
+    pub max_open_connections: usize,
+    /// The minimum number of idle connections the pool keeps open.
+    pub min_idle_connections: usize,
+    /// How long an acquirer waits for a free connection, [`None`] waits forever.
+    pub acquire_timeout: Option<Duration>,
+    /// Connections older than this are closed instead of being reused.
+    pub max_lifetime: Option<Duration>,
+    /// Idle connections unused longer than this are closed,
+    /// unless the pool would drop below `min_idle_connections`.
+    pub idle_timeout: Option<Duration>,
+    /// When to ping an idle connection before handing it out.
+    pub validation: Validation,
+    /// Prepared statements cached per connection, `0` disables the cache.
+    pub statement_cache_capacity: usize,
+}
+
+/// Checkout validation policy, see [`crate::driver::Connection::ping`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Validation {
+    /// Ping every idle connection before reusing it.
+    Always,
+    /// Ping connections that have been idle for at least this long.
+    AfterIdle(Duration),
+    /// Never ping, only [`crate::driver::Connection::is_valid`] is checked.
+    Never,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            max_open_connections: 10,
+            min_idle_connections: 0,
+            acquire_timeout: Some(Duration::from_secs(30)),
+            max_lifetime: None,
+            idle_timeout: None,
+            validation: Validation::AfterIdle(Duration::from_secs(30)),
+            statement_cache_capacity: 100,
+        }
+    }
+}
+
+/// Point-in-time connection pool counters, returned by [`crate::Database::pool_status`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// Connections opened by the pool, including checked out and idle ones.
+    pub open_connections: usize,
+    /// Connections waiting in the pool to be reused.
+    pub idle_connections: usize,
+    /// Acquirers waiting for a connection to be returned.
+    pub waiters: usize,
+    /// Reused connections the driver had to clean up (e.g. an open transaction),
+    /// counted since the pool was created.
+    pub reset_connections: u64,
+}
+
+/// Prepared statement cache counters of all the pool connections,
+/// returned by [`crate::Database::statement_cache_status`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementCacheStatus {
+    /// Prepares served by a cached statement
+    pub hits: u64,
+    /// Prepares that had to prepare a new statement
+    pub misses: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum PoolError {
+    #[error("acquire connection timeout after {0:?}")]
+    AcquireTimeout(Duration),
+
+    #[error("driver {0} not found")]
+    DriverNotFound(String),
+}
+
+/// Driver connection owned by the pool
+struct Entry {
+    /// Declared before `conn`, cached statements are closed first
+    cache: Arc<Mutex<StatementCache>>,
+    conn: Box<dyn driver::Connection>,
+    created_at: Instant,
+    idle_since: Instant,
+    /// Pending session reset started when the connection was returned
+    reset: Option<driver::Reset>,
+}
+
+/// What a waiting acquirer is handed by the pool
+enum Grant {
+    /// Reuse a returned connection
+    Connection(Entry),
+    /// Permission to open a new connection
+    Slot,
+}
+
+struct Waiter {
+    id: u64,
+    deadline: Option<Instant>,
+    waker: waker::SharedWaker<Result<Grant>>,
+}
+
+#[derive(Default)]
+struct State {
+    idle: VecDeque<Entry>,
+    /// Number of opened (or opening) connections, idle and checked out
+    open: usize,
+    /// FIFO queue of acquirers waiting for a connection
+    waiters: VecDeque<Waiter>,
+    next_waiter_id: u64,
+    /// When the pool maintenance is scheduled to run next
+    maintain_at: Option<Instant>,
+    /// A thread is opening the missing `min_idle_connections`
+    refilling: bool,
+    reset_connections: u64,
+}
+
+impl State {
+    /// Hands a freed connection slot to the first waiter, or gives it back.
+    fn free_slot(&mut self) {
+        match self.waiters.pop_front() {
+            Some(waiter) => waiter.waker.lock().unwrap().ready(Ok(Grant::Slot)),
+            None => self.open -= 1,
+        }
+    }
+}
+
+/// Bounded connection pool shared by [`crate::Database`] and its statements/transactions.
+///
+/// Expired waiters and connections are cleaned up by jobs on the shared [`timer`] thread.
+pub(crate) struct Pool {
+    this: Weak<Pool>,
+    name: String,
+    url: String,
+    drivers: Arc<Mutex<HashMap<String, Box<dyn driver::Driver>>>>,
+    options: PoolOptions,
+    state: Mutex<State>,
+    cache_counters: Arc<CacheCounters>,
+}
+
+impl Pool {
+    pub(crate) fn new(
+        name: &str,
+        url: &str,
+        drivers: Arc<Mutex<HashMap<String, Box<dyn driver::Driver>>>>,
+        options: PoolOptions,
+    ) -> Arc<Self> {
+        let pool = Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            name: name.to_owned(),
+            url: url.to_owned(),
+            drivers,
+            options,
+            state: Default::default(),
+            cache_counters: Default::default(),
+        });
+
+        if pool.options.min_idle_connections > 0 {
+            let mut state = pool.state.lock().unwrap();
+
+            pool.schedule_maintain(&mut state, Some(Instant::now()));
+        }
+
+        pool
+    }
+
+    pub(crate) fn options(&self) -> &PoolOptions {
+        &self.options
+    }
+
+    pub(crate) fn status(&self) -> PoolStatus {
+        let state = self.state.lock().unwrap();
+
+        PoolStatus {
+            open_connections: state.open,
+            idle_connections: state.idle.len(),
+            waiters: state.waiters.len(),
+            reset_connections: state.reset_connections,
+        }
+    }
+
+    pub(crate) fn statement_cache_status(&self) -> StatementCacheStatus {
+        StatementCacheStatus {
+            hits: self.cache_counters.hits(),
+            misses: self.cache_counters.misses(),
+        }
+    }
+
+    /// Checkout one connection, waiting in FIFO order if the pool is exhausted.
+    pub(crate) async fn acquire(self: &Arc<Self>) -> Result<PooledConnection> {
+        let grant = match self.try_acquire() {
+            Ok(grant) => grant,
+            Err((id, fut)) => {
+                let mut guard = WaitGuard {
+                    pool: self,
+                    id,
+                    waker: fut.waker.clone(),
+                    armed: true,
+                };
+
+                let grant = fut.await;
+
+                guard.armed = false;
+
+                grant?
+            }
+        };
+
+        if let Grant::Connection(mut entry) = grant {
+            let validate = self.need_validation(&entry);
+
+            let reset = entry.reset.take();
+
+            let mut conn = PooledConnection {
+                entry: Some(entry),
+                pool: self.clone(),
+            };
+
+            let checked = match reset {
+                Some(reset) => reset.await.map(|cleaned| {
+                    if cleaned {
+                        self.state.lock().unwrap().reset_connections += 1;
+
+                        // cached statements may refer to dropped temp objects
+                        conn.statement_cache().lock().unwrap().clear();
+                    }
+                }),
+                None => Ok(()),
+            };
+
+            let checked = match checked {
+                Ok(()) if validate => conn.inner().ping().await,
+                checked => checked,
+            };
+
+            match checked {
+                Ok(()) => return Ok(conn),
+                Err(err) => {
+                    log::warn!("evict broken connection {}: {}", conn.inner().id(), err);
+
+                    // close the broken connection and keep its slot to open a new one
+                    conn.entry.take();
+                }
+            }
+        }
+
+        let mut guard = SlotGuard {
+            pool: self,
+            armed: true,
+        };
+
+        let entry = self.connect().await;
+
+        guard.armed = entry.is_err();
+
+        Ok(PooledConnection {
+            entry: Some(entry?),
+            pool: self.clone(),
+        })
+    }
+
+    fn need_validation(&self, entry: &Entry) -> bool {
+        match self.options.validation {
+            Validation::Always => true,
+            Validation::AfterIdle(idle) => entry.idle_since.elapsed() >= idle,
+            Validation::Never => false,
+        }
+    }
+
+    /// Take an idle connection or a free slot, otherwise register a waiter.
+    fn try_acquire(
+        &self,
+    ) -> std::result::Result<Grant, (u64, waker::WakableFuture<Result<Grant>>)> {
+        let now = Instant::now();
+
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(entry) = state.idle.pop_back() {
+            if entry.conn.is_valid() && !self.lifetime_expired(&entry, now) {
+                if state.idle.len() < self.options.min_idle_connections {
+                    self.schedule_maintain(&mut state, Some(now));
+                }
+
+                return Ok(Grant::Connection(entry));
+            }
+
+            // close the connection outside the lock, and reuse its slot
+            drop(state);
+
+            log::debug!("close expired or broken connection {}", entry.conn.id());
+
+            drop(entry);
+
+            return Ok(Grant::Slot);
+        }
+
+        if state.open < self.options.max_open_connections {
+            state.open += 1;
+
+            return Ok(Grant::Slot);
+        }
+
+        let (fut, waker) = waker::WakableFuture::new();
+
+        let id = state.next_waiter_id;
+
+        state.next_waiter_id += 1;
+
+        let deadline = self.options.acquire_timeout.map(|timeout| now + timeout);
+
+        state.waiters.push_back(Waiter {
+            id,
+            deadline,
+            waker,
+        });
+
+        self.schedule_maintain(&mut state, deadline);
+
+        Err((id, fut))
+    }
+
+    /// Open a new driver connection, the caller must own a connection slot.
+    async fn connect(&self) -> Result<Entry> {
+        let fut = {
+            let mut drivers = self.drivers.lock().unwrap();
+
+            drivers
+                .get_mut(&self.name)
+                .map(|driver| driver.open(&self.url))
+        };
+
+        let conn = match fut {
+            Some(fut) => fut.await?,
+            None => return Err(Error::from(PoolError::DriverNotFound(self.name.clone()))),
+        };
+
+        let now = Instant::now();
+
+        let cache = StatementCache::new(
+            self.options.statement_cache_capacity,
+            self.cache_counters.clone(),
+        );
+
+        Ok(Entry {
+            cache: Arc::new(Mutex::new(cache)),
+            conn,
+            created_at: now,
+            idle_since: now,
+            reset: None,
+        })
+    }
+
+    /// Return a connection to the pool, handing it to the first waiter if any.
+    ///
+    /// The driver session reset starts here, and is awaited by the next acquirer.
+    fn release(&self, mut entry: Entry) {
+        let now = Instant::now();
+
+        if !entry.conn.is_valid() || self.lifetime_expired(&entry, now) {
+            self.release_slot();
+
+            log::debug!("close expired or broken connection {}", entry.conn.id());
+
+            return;
+        }
+
+        entry.reset = Some(entry.conn.reset());
+
+        entry.idle_since = now;
+
+        let mut state = self.state.lock().unwrap();
+
+        match state.waiters.pop_front() {
+            Some(waiter) => waiter
+                .waker
+                .lock()
+                .unwrap()
+                .ready(Ok(Grant::Connection(entry))),
+            None => {
+                let deadline = earliest(
+                    self.options.idle_timeout.map(|timeout| now + timeout),
+                    self.options
+                        .max_lifetime
+                        .map(|lifetime| entry.created_at + lifetime),
+                );
+
+                state.idle.push_back(entry);
+
+                self.schedule_maintain(&mut state, deadline);
+            }
+        }
+    }
+
+    fn release_slot(&self) {
+        self.state.lock().unwrap().free_slot();
+    }
+
+    /// Called when an acquire future is dropped before it completes.
+    fn cancel_wait(&self, id: u64, waker: &waker::SharedWaker<Result<Grant>>) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(index) = state.waiters.iter().position(|waiter| waiter.id == id) {
+            state.waiters.remove(index);
+            return;
+        }
+
+        drop(state);
+
+        // The pool already granted something to this waiter, give it back.
+        let output = waker.lock().unwrap().output.take();
+
+        match output {
+            Some(Ok(Grant::Connection(entry))) => self.release(entry),
+            Some(Ok(Grant::Slot)) => self.release_slot(),
+            _ => {}
+        }
+    }
+
+    fn lifetime_expired(&self, entry: &Entry, now: Instant) -> bool {
+        match self.options.max_lifetime {
+            Some(max_lifetime) => now.duration_since(entry.created_at) >= max_lifetime,
+            None => false,
+        }
+    }
+
+    fn idle_expired(&self, entry: &Entry, now: Instant) -> bool {
+        match self.options.idle_timeout {
+            Some(idle_timeout) => now.duration_since(entry.idle_since) >= idle_timeout,
+            None => false,
+        }
+    }
+
+    /// Schedule the pool maintenance at `deadline`, unless it already runs earlier.
+    fn schedule_maintain(&self, state: &mut State, deadline: Option<Instant>) {
+        let Some(deadline) = deadline else {
+            return;
+        };
+
+        if state.maintain_at.is_some_and(|at| at <= deadline) {
+            return;
+        }
+
+        state.maintain_at = Some(deadline);
+
+        let pool = self.this.clone();
+
+        let scheduled = timer::schedule(deadline, move || {
+            if let Some(pool) = pool.upgrade() {
+                pool.maintain(deadline);
+            }
+        });
+
+        if let Err(err) = scheduled {
+            log::error!("schedule pool maintenance failed: {}", err);
+
+            state.maintain_at = None;
+        }
+    }
+
+    /// Expire timed out waiters, close expired idle connections and start
+    /// refilling `min_idle_connections`, then schedule the next run.
+    ///
+    /// Runs on the timer thread, `at` is the deadline it was scheduled for.
+    fn maintain(&self, at: Instant) {
+        let now = Instant::now();
+
+        let mut closed = vec![];
+
+        let refill = {
+            let mut state = self.state.lock().unwrap();
+
+            // superseded by an earlier run, which scheduled its own next run
+            if state.maintain_at != Some(at) {
+                return;
+            }
+
+            state.maintain_at = None;
+
+            let mut waiters = VecDeque::with_capacity(state.waiters.len());
+
+            for waiter in state.waiters.drain(..) {
+                match waiter.deadline {
+                    Some(deadline) if deadline <= now => {
+                        let timeout = self.options.acquire_timeout.unwrap_or_default();
+
+                        waiter
+                            .waker
+                            .lock()
+                            .unwrap()
+                            .ready(Err(Error::from(PoolError::AcquireTimeout(timeout))));
+                    }
+                    _ => waiters.push_back(waiter),
+                }
+            }
+
+            state.waiters = waiters;
+
+            let mut idle = VecDeque::with_capacity(state.idle.len());
+
+            while let Some(entry) = state.idle.pop_front() {
+                let idle_expired = self.idle_expired(&entry, now)
+                    && state.idle.len() + idle.len() >= self.options.min_idle_connections;
+
+                if idle_expired || self.lifetime_expired(&entry, now) {
+                    state.free_slot();
+                    closed.push(entry);
+                    continue;
+                }
+
+                idle.push_back(entry);
+            }
+
+            state.idle = idle;
+
+            let refill = if state.refilling {
+                0
+            } else {
+                self.options
+                    .min_idle_connections
+                    .saturating_sub(state.idle.len())
+                    .min(self.options.max_open_connections - state.open)
+            };
+
+            state.open += refill;
+
+            state.refilling = refill > 0;
+
+            let mut next = None;
+
+            for waiter in &state.waiters {
+                next = earliest(next, waiter.deadline);
+            }
+
+            for entry in &state.idle {
+                next = earliest(
+                    next,
+                    self.options
+                        .idle_timeout
+                        .map(|timeout| entry.idle_since + timeout),
+                );
+
+                next = earliest(
+                    next,
+                    self.options
+                        .max_lifetime
+                        .map(|lifetime| entry.created_at + lifetime),
+                );
+            }
+
+            self.schedule_maintain(&mut state, next);
+
+            refill
+        };
+
+        for entry in closed {
+            log::debug!("close idle connection {}", entry.conn.id());
+        }
+
+        if refill > 0 {
+            self.refill(refill);
+        }
+    }
+
+    /// Open `count` connections for `min_idle_connections`, the slots are already taken.
+    ///
+    /// Opening may block, so it runs on its own short-lived thread instead of the timer thread.
+    fn refill(&self, count: usize) {
+        let Some(pool) = self.this.upgrade() else {
+            return;
+        };
+
+        let handle = std::thread::Builder::new()
+            .name(format!("rdbc-pool-{}", self.name))
+            .spawn(move || {
+                let mut failed = false;
+
+                for _ in 0..count {
+                    match futures::executor::block_on(pool.connect()) {
+                        Ok(entry) => pool.release(entry),
+                        Err(err) => {
+                            log::error!("open min idle connection failed: {}", err);
+
+                            pool.release_slot();
+
+                            failed = true;
+                        }
+                    }
+                }
+
+                let mut state = pool.state.lock().unwrap();
+
+                state.refilling = false;
+
+                if failed {
+                    pool.schedule_maintain(
+                        &mut state,
+                        Some(Instant::now() + MAINTAIN_RETRY_INTERVAL),
+                    );
+                }
+            });
+
+        if let Err(err) = handle {
+            log::error!("spawn pool refill thread failed: {}", err);
+
+            let mut state = self.state.lock().unwrap();
+
+            for _ in 0..count {
+                state.free_slot();
+            }
+
+            state.refilling = false;
+
+            self.schedule_maintain(&mut state, Some(Instant::now() + MAINTAIN_RETRY_INTERVAL));
+        }
+    }
+}
+
+fn earliest(lhs: Option<Instant>, rhs: Option<Instant>) -> Option<Instant> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(lhs.min(rhs)),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+/// Removes the waiter from the pool if the acquire future is dropped.
+struct WaitGuard<'a> {
+    pool: &'a Pool,
+    id: u64,
+    waker: waker::SharedWaker<Result<Grant>>,
+    armed: bool,
+}
+
+impl<'a> Drop for WaitGuard<'a> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.pool.cancel_wait(self.id, &self.waker);
+        }
+    }
+}
+
+/// Gives the connection slot back if opening the connection fails or is cancelled.
+struct SlotGuard<'a> {
+    pool: &'a Pool,
+    armed: bool,
+}
+
+impl<'a> Drop for SlotGuard<'a> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.pool.release_slot();
+        }
+    }
+}
+
+/// Connection checked out from [`Pool`], returned to the pool when dropped.
+pub(crate) struct PooledConnection {
+    entry: Option<Entry>,
+    pool: Arc<Pool>,
+}
+
+// SAFETY: the driver connection, the only field that isn't `Sync`, is only
+// reachable through `&mut self` (see `inner`), so sharing `&PooledConnection`
+// between threads gives no access to it.
+unsafe impl Sync for PooledConnection {}
+
+impl PooledConnection {
+    /// The prepared statement cache of this connection
+    pub(crate) fn statement_cache(&self) -> Arc<Mutex<StatementCache>> {
+        self.entry.as_ref().unwrap().cache.clone()
+    }
+
+    /// The driver connection, requires `&mut self` to keep `PooledConnection` `Sync`
+    pub(crate) fn inner(&mut self) -> &mut dyn driver::Connection {
+        self.entry.as_mut().unwrap().conn.as_mut()
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            self.pool.release(entry);
+        }
+    }
+}
@@ -1,15 +1,72 @@
 use super::driver;
 use super::driver::*;
-use anyhow::*;
+use super::error::{Error, Result};
+use super::from_row::FromRow;
+use super::value::FromValue;
+use futures::{Stream, TryStreamExt};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
-/// [`driver::Rows`] wrapper
-pub struct Rows {
+#[derive(thiserror::Error, Debug)]
+pub enum RowsError {
+    #[error("query returned no rows")]
+    NoRows,
+
+    #[error("unknown column {0}")]
+    UnknownColumn(String),
+
+    #[error("column index {0} out of range")]
+    OutOfRange(u64),
+}
+
+/// Rows fetched per driver call by the [`Rows`] stream
+pub const ROWS_PREFETCH: usize = 64;
+
+/// [`driver::Rows`] wrapper, borrowing the [`crate::Statement`] that produced it
+///
+/// `Rows` is also a [`Stream`] of [`Row`] snapshots, fetched [`ROWS_PREFETCH`]
+/// rows per driver call. The inherent [`Rows::next`] method shadows
+/// `StreamExt::next`, so iterate with `TryStreamExt::try_next` or
+/// `StreamExt::next(&mut rows)`. Don't mix the stream with the inherent
+/// [`Rows::next`] on the same rows, the stream may have fetched rows ahead.
+pub struct Rows<'s> {
     inner: Box<dyn driver::Rows>,
+    columns: Option<Arc<Vec<ColumnMetaData>>>,
+    state: StreamState,
+    /// Rows fetched by the stream and not returned yet
+    prefetched: VecDeque<Vec<Value>>,
+    /// The last [`driver::Rows::next_batch`] returned the last rows
+    exhausted: bool,
+    /// Rows fetched per [`driver::Rows::next_batch`] call
+    batch_size: usize,
+    _stmt: PhantomData<&'s mut ()>,
+}
+
+/// Pending driver call of the [`Rows`] stream
+enum StreamState {
+    Idle,
+    NextBatch(RowsNextBatch),
+    Columns(Columns),
+    Done,
 }
 
-impl Rows {
+impl<'s> Rows<'s> {
     pub(crate) fn new(inner: Box<dyn driver::Rows>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            columns: None,
+            state: StreamState::Idle,
+            prefetched: VecDeque::new(),
+            exhausted: false,
+            batch_size: ROWS_PREFETCH,
+            _stmt: PhantomData,
+        }
     }
 
     pub async fn colunms(&mut self) -> Result<Vec<ColumnMetaData>> {
@@ -26,4 +83,183 @@ impl Rows {
     {
         self.inner.get(pos.into(), column_type).await
     }
+
+    /// Snapshot of the current row, all values fetched in one driver call
+    pub async fn get_row(&mut self) -> Result<Row> {
+        let columns = match &self.columns {
+            Some(columns) => columns.clone(),
+            None => {
+                let columns = Arc::new(self.inner.colunms().await?);
+
+                self.columns = Some(columns.clone());
+
+                columns
+            }
+        };
+
+        let values = self.inner.get_row().await?;
+
+        Ok(Row { columns, values })
+    }
+
+    /// Collect all remaining rows
+    pub async fn fetch_all<T>(mut self) -> Result<Vec<T>>
+    where
+        T: FromRow,
+    {
+        let mut rows = vec![];
+
+        while let Some(row) = self.try_next().await? {
+            rows.push(T::from_row(&row)?);
+        }
+
+        Ok(rows)
+    }
+
+    /// Fetch the first row, returns [`RowsError::NoRows`] if the result is empty.
+    ///
+    /// The rows after the first one are not fetched, like [`Rows::fetch_optional`].
+    pub async fn fetch_one<T>(self) -> Result<T>
+    where
+        T: FromRow,
+    {
+        self.fetch_optional()
+            .await?
+            .ok_or_else(|| Error::from(RowsError::NoRows))
+    }
+
+    /// Fetch the first row if any
+    pub async fn fetch_optional<T>(mut self) -> Result<Option<T>>
+    where
+        T: FromRow,
+    {
+        // don't step the statement further than needed
+        self.batch_size = 1;
+
+        match self.try_next().await? {
+            Some(row) => Ok(Some(T::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'s> Stream for Rows<'s> {
+    type Item = Result<Row>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(columns) = &this.columns {
+                if let Some(values) = this.prefetched.pop_front() {
+                    return Poll::Ready(Some(Ok(Row {
+                        columns: columns.clone(),
+                        values,
+                    })));
+                }
+            }
+
+            match &mut this.state {
+                StreamState::Idle if this.exhausted => {
+                    this.state = StreamState::Done;
+                }
+                StreamState::Idle => {
+                    this.state = StreamState::NextBatch(this.inner.next_batch(this.batch_size));
+                }
+                StreamState::NextBatch(next_batch) => match Pin::new(next_batch).poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Err(err)) => {
+                        this.state = StreamState::Done;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    Poll::Ready(Ok(rows)) => {
+                        this.exhausted = rows.len() < this.batch_size;
+
+                        this.prefetched.extend(rows);
+
+                        // the driver knows the column types once a row is fetched
+                        if this.columns.is_none() && !this.prefetched.is_empty() {
+                            this.state = StreamState::Columns(this.inner.colunms());
+                        } else {
+                            this.state = StreamState::Idle;
+                        }
+                    }
+                },
+                StreamState::Columns(columns) => match Pin::new(columns).poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Err(err)) => {
+                        this.state = StreamState::Done;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    Poll::Ready(Ok(columns)) => {
+                        this.columns = Some(Arc::new(columns));
+
+                        this.state = StreamState::Idle;
+                    }
+                },
+                StreamState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Snapshot of one result row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    columns: Arc<Vec<ColumnMetaData>>,
+    values: Vec<Value>,
+}
+
+impl Row {
+    pub fn columns(&self) -> &[ColumnMetaData] {
+        &self.columns
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Vec<Value> {
+        self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Get column value by index or column name
+    pub fn get<Pos>(&self, pos: Pos) -> Option<&Value>
+    where
+        Pos: Into<Placeholder>,
+    {
+        match pos.into() {
+            Placeholder::Index(index) => self.values.get(index as usize),
+            Placeholder::Name(name) => self
+                .columns
+                .iter()
+                .position(|c| c.column_name == name)
+                .and_then(|index| self.values.get(index)),
+        }
+    }
+
+    /// Get column value by index or column name and convert it to `T`
+    pub fn try_get<T, Pos>(&self, pos: Pos) -> Result<T>
+    where
+        T: FromValue,
+        Pos: Into<Placeholder>,
+    {
+        let pos = pos.into();
+
+        match self.get(pos.clone()) {
+            Some(value) => T::from_value(value),
+            None => match pos {
+                Placeholder::Index(index) => Err(Error::from(RowsError::OutOfRange(index))),
+                Placeholder::Name(name) => Err(Error::from(RowsError::UnknownColumn(name))),
+            },
+        }
+    }
 }
@@ -1,33 +1,70 @@
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+use std::{marker::PhantomData, sync::Arc, time::Duration};
 
+use futures::{Stream, StreamExt};
+
+use super::cache::CachedStatement;
 use super::driver;
-use super::driver::Arg;
+use super::error::Result;
+use super::params::Params;
+use super::pool::PooledConnection;
 use super::rows::*;
+use super::transaction::Transaction;
 use super::waker;
-use anyhow::Result;
+
+/// Rows per driver call of [`Statement::execute_stream`]
+pub const EXECUTE_STREAM_BATCH_SIZE: usize = 512;
+
+/// Future of [`Statement::execute_returning`]
+pub type ExecuteReturning<'s> = waker::WakableMapFuture<
+    Result<(driver::ExecuteResult, Rows<'s>)>,
+    Result<(driver::ExecuteResult, Box<dyn driver::Rows>)>,
+>;
 
 /// The [`driver::Statement`] wrapper
-pub struct Statement {
-    conn: Option<Box<dyn driver::Connection>>,
-    statement: Box<dyn driver::Statement>,
-    connection_pool: Arc<Mutex<HashMap<String, Box<dyn driver::Connection>>>>,
+///
+/// A statement prepared by [`crate::Database::prepare`] owns its connection and is
+/// `Statement<'static>`, one prepared by [`Transaction::prepare`] borrows the
+/// transaction, which can't commit or rollback before the statement is dropped.
+///
+/// ```compile_fail
+/// # async fn f(db: &mut rdbc::Database) {
+/// let mut tx = db.begin().await.unwrap();
+///
+/// let mut stmt = tx.prepare("INSERT INTO t VALUES(1)").await.unwrap();
+///
+/// tx.commit().await.unwrap();
+///
+/// stmt.execute(()).await.unwrap();
+/// # }
+/// ```
+///
+/// The [`Rows`] returned by [`Statement::query`] can't outlive the statement:
+///
+/// ```compile_fail
+/// # async fn f(db: &mut rdbc::Database) {
+/// let rows = {
+///     let mut stmt = db.prepare("SELECT 1").await.unwrap();
+///
+///     stmt.query(()).await.unwrap()
+/// };
+/// # }
+/// ```
+///
+/// When dropped, the driver statement is put back in the statement cache of
+/// its connection, see [`crate::PoolOptions::statement_cache_capacity`].
+pub struct Statement<'c> {
+    statement: CachedStatement,
+    /// Returned to the pool when dropped, after `statement` is cached.
+    _conn: Option<PooledConnection>,
+    _tx: PhantomData<&'c Transaction>,
 }
 
-unsafe impl Send for Statement {}
-
-impl Statement {
-    pub(crate) fn new(
-        connection_pool: Arc<Mutex<HashMap<String, Box<dyn driver::Connection>>>>,
-        conn: Option<Box<dyn driver::Connection>>,
-        statement: Box<dyn driver::Statement>,
-    ) -> Self {
+impl<'c> Statement<'c> {
+    pub(crate) fn new(conn: Option<PooledConnection>, statement: CachedStatement) -> Self {
         Statement {
-            connection_pool,
-            conn,
             statement,
+            _conn: conn,
+            _tx: PhantomData,
         }
     }
 
@@ -35,32 +72,158 @@ impl Statement {
         self.statement.num_input()
     }
 
+    /// Limit the run time of each execute, query and row fetch of this statement,
+    /// overriding [`crate::Database::set_query_timeout`]. [`None`] disables the limit.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.statement.set_timeout(timeout)
+    }
+
+    /// Returns a handle that cancels the operation running on this statement
+    /// from another task or thread.
+    ///
+    /// Dropping a pending execute, query or row fetch future doesn't cancel the
+    /// operation, the driver runs it to completion and discards the result.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            inner: self.statement.cancel_handle(),
+        }
+    }
+
     /// Executes a query that doesn't return rows, such
     /// as an INSERT or UPDATE.
-    pub fn execute(&mut self, args: Vec<Arg>) -> driver::Execute {
-        self.statement.execute(args)
+    pub fn execute(&mut self, params: impl Params) -> driver::Execute {
+        match params.into_args() {
+            Ok(args) => self.statement.execute(args),
+            Err(err) => {
+                let (fut, waker) = driver::Execute::new();
+
+                waker.lock().unwrap().ready(Err(err));
+
+                fut
+            }
+        }
+    }
+
+    /// Executes the statement once for each parameters of `batch` in a single
+    /// driver call, see [`driver::Statement::execute_batch`].
+    pub fn execute_batch<I, P>(&mut self, batch: I) -> driver::ExecuteBatch
+    where
+        I: IntoIterator<Item = P>,
+        P: Params,
+    {
+        let batch = batch
+            .into_iter()
+            .map(|params| params.into_args())
+            .collect::<Result<Vec<_>>>();
+
+        match batch {
+            Ok(batch) => self.statement.execute_batch(batch),
+            Err(err) => {
+                let (fut, waker) = driver::ExecuteBatch::new();
+
+                waker.lock().unwrap().ready(Err(err));
+
+                fut
+            }
+        }
+    }
+
+    /// Executes the statement once for each parameters of `stream`, sent to the
+    /// driver in batches of [`EXECUTE_STREAM_BATCH_SIZE`].
+    ///
+    /// Returns the aggregate result: the sum of the affected rows and the last
+    /// insert id of the last execution. An iterator can be passed with
+    /// [`futures::stream::iter`].
+    pub async fn execute_stream<S, P>(&mut self, stream: S) -> Result<driver::ExecuteResult>
+    where
+        S: Stream<Item = P>,
+        P: Params,
+    {
+        let mut batches = std::pin::pin!(stream.chunks(EXECUTE_STREAM_BATCH_SIZE));
+
+        let mut result = driver::ExecuteResult {
+            last_insert_id: 0,
+            raws_affected: 0,
+        };
+
+        let mut executed = 0;
+
+        while let Some(batch) = batches.next().await {
+            let len = batch.len();
+
+            let results = self
+                .execute_batch(batch)
+                .await
+                .map_err(|err| err.context(format!("execute stream after {} rows", executed)))?;
+
+            for row in results {
+                result.last_insert_id = row.last_insert_id;
+                result.raws_affected += row.raws_affected;
+            }
+
+            executed += len;
+        }
+
+        Ok(result)
     }
 
     /// executes a query that may return rows, such as a
     /// SELECT.
+    ///
+    /// The returned [`Rows`] borrow this statement, which can't be executed
+    /// again or dropped while they are in use.
     pub fn query(
         &mut self,
-        args: Vec<Arg>,
-    ) -> waker::WakableMapFuture<Result<Rows>, Result<Box<dyn driver::Rows>>> {
-        self.statement.query(args).map(|r| match r {
+        params: impl Params,
+    ) -> waker::WakableMapFuture<Result<Rows<'_>>, Result<Box<dyn driver::Rows>>> {
+        let query = match params.into_args() {
+            Ok(args) => self.statement.query(args),
+            Err(err) => {
+                let (fut, waker) = driver::Query::new();
+
+                waker.lock().unwrap().ready(Err(err));
+
+                fut
+            }
+        };
+
+        query.map(|r| match r {
             Ok(rows) => Ok(Rows::new(rows)),
             Err(err) => Err(err),
         })
     }
+
+    /// Executes an INSERT, UPDATE or DELETE with a RETURNING clause, and
+    /// returns its result together with the returned rows.
+    ///
+    /// The returned [`Rows`] borrow this statement like [`Statement::query`] ones.
+    pub fn execute_returning(&mut self, params: impl Params) -> ExecuteReturning<'_> {
+        let execute = match params.into_args() {
+            Ok(args) => self.statement.execute_returning(args),
+            Err(err) => {
+                let (fut, waker) = driver::ExecuteReturning::new();
+
+                waker.lock().unwrap().ready(Err(err));
+
+                fut
+            }
+        };
+
+        execute.map(|r| r.map(|(result, rows)| (result, Rows::new(rows))))
+    }
 }
 
-impl Drop for Statement {
-    fn drop(&mut self) {
-        if let Some(conn) = self.conn.take() {
-            self.connection_pool
-                .lock()
-                .unwrap()
-                .insert(conn.id().to_owned(), conn);
-        }
+/// Cancels the operation running on a [`Statement`], see [`Statement::cancel_handle`]
+#[derive(Clone)]
+pub struct CancelHandle {
+    inner: Arc<dyn driver::CancelHandle>,
+}
+
+impl CancelHandle {
+    /// Interrupt the running execute, query or row fetch of the statement, which
+    /// fails with [`crate::ErrorKind::Cancelled`]. Does nothing if none is running,
+    /// or once the statement is dropped.
+    pub async fn cancel(&self) -> Result<()> {
+        self.inner.cancel().await
     }
 }
@@ -0,0 +1,126 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Condvar, Mutex, OnceLock},
+    time::Instant,
+};
+
+/// Callback run by the timer thread once its deadline is reached
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Timeout {
+    at: Instant,
+    /// Keeps jobs with the same deadline in scheduling order
+    seq: u64,
+    job: Job,
+}
+
+impl PartialEq for Timeout {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Timeout {}
+
+impl PartialOrd for Timeout {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timeout {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Earliest deadline first
+    heap: BinaryHeap<Reverse<Timeout>>,
+    next_seq: u64,
+    started: bool,
+}
+
+/// Deadline heap served by one process wide thread, shared by the maintenance
+/// of every connection pool and [`crate::waker::delay`].
+///
+/// Jobs run on the timer thread one after the other, so they must not block.
+#[derive(Default)]
+struct Timer {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+fn timer() -> &'static Timer {
+    static TIMER: OnceLock<Timer> = OnceLock::new();
+
+    TIMER.get_or_init(Default::default)
+}
+
+/// Run `job` on the timer thread at `at`, or as soon as possible if `at` is past.
+///
+/// Fails if the timer thread can't be started, `job` is dropped without running.
+pub(crate) fn schedule(at: Instant, job: impl FnOnce() + Send + 'static) -> std::io::Result<()> {
+    let timer = timer();
+
+    let mut state = timer.state.lock().unwrap();
+
+    if !state.started {
+        std::thread::Builder::new()
+            .name("rdbc-timer".to_owned())
+            .spawn(move || run(timer))?;
+
+        state.started = true;
+    }
+
+    let seq = state.next_seq;
+
+    state.next_seq += 1;
+
+    let wake = state.heap.peek().is_none_or(|Reverse(next)| at < next.at);
+
+    state.heap.push(Reverse(Timeout {
+        at,
+        seq,
+        job: Box::new(job),
+    }));
+
+    if wake {
+        timer.changed.notify_one();
+    }
+
+    Ok(())
+}
+
+fn run(timer: &Timer) {
+    let mut state = timer.state.lock().unwrap();
+
+    loop {
+        let now = Instant::now();
+
+        let next = state.heap.peek().map(|Reverse(next)| next.at);
+
+        match next {
+            Some(at) if at <= now => {
+                let Reverse(timeout) = state.heap.pop().unwrap();
+
+                drop(state);
+
+                if catch_unwind(AssertUnwindSafe(timeout.job)).is_err() {
+                    log::error!("timer job panicked");
+                }
+
+                state = timer.state.lock().unwrap();
+            }
+            Some(at) => {
+                state = timer.changed.wait_timeout(state, at - now).unwrap().0;
+            }
+            None => {
+                state = timer.changed.wait(state).unwrap();
+            }
+        }
+    }
+}
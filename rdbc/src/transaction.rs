@@ -1,55 +1,264 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use super::cache::{CachedStatement, StatementCache};
 use super::driver;
+use super::error::Result;
+use super::pool::PooledConnection;
 use super::statement::*;
-use anyhow::*;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 
-/// The [`driver::Transaction`] wrapper
+/// The [`driver::Transaction`] wrapper, owning its connection until dropped
+///
+/// Statements prepared by the transaction borrow it, so it can't commit or
+/// rollback before they are dropped.
 pub struct Transaction {
-    inner: Box<dyn driver::Transaction>,
-    connection_pool: Arc<Mutex<HashMap<String, Box<dyn driver::Connection>>>>,
-    conn: Option<Box<dyn driver::Connection>>,
+    inner: Mutex<Box<dyn driver::Transaction>>,
+    /// Returned to the pool when dropped, after `inner` is closed.
+    _conn: PooledConnection,
+    /// See [`crate::Database::set_query_timeout`]
+    query_timeout: Option<Duration>,
+    /// Statement cache of the transaction connection
+    cache: Arc<Mutex<StatementCache>>,
+    /// Set by a successful commit, the cache is cleared on rollback
+    committed: bool,
+    /// Whether the transaction changed the schema, reported by the driver
+    schema_changes: Arc<dyn driver::SchemaChanges>,
 }
 
 impl Transaction {
     pub(crate) fn new(
-        connection_pool: Arc<Mutex<HashMap<String, Box<dyn driver::Connection>>>>,
-        conn: Option<Box<dyn driver::Connection>>,
+        conn: PooledConnection,
         inner: Box<dyn driver::Transaction>,
+        query_timeout: Option<Duration>,
     ) -> Self {
         Self {
-            inner,
-            connection_pool,
-            conn,
+            schema_changes: inner.schema_changes(),
+            inner: Mutex::new(inner),
+            cache: conn.statement_cache(),
+            _conn: conn,
+            query_timeout,
+            committed: false,
         }
     }
 
-    pub async fn prepare(&mut self, query: &str) -> Result<Statement> {
-        let statement = self.inner.prepare(query).await?;
+    fn inner(&self) -> MutexGuard<'_, Box<dyn driver::Transaction>> {
+        self.inner.lock().unwrap()
+    }
+
+    pub async fn prepare(&self, query: &str) -> Result<Statement<'_>> {
+        let statement =
+            CachedStatement::prepare(self.cache.clone(), query, self.query_timeout, || {
+                self.inner().prepare(query)
+            })
+            .await?;
+
+        Ok(Statement::new(None, statement))
+    }
+
+    /// Runs every statement of `script` within this transaction,
+    /// see [`driver::Connection::execute_script`].
+    pub async fn execute_script(&self, script: &str) -> Result<driver::ExecuteResult> {
+        let execute = self.inner().execute_script(script);
 
-        Ok(Statement::new(
-            self.connection_pool.clone(),
-            None,
-            statement,
-        ))
+        execute.await
     }
 
     pub async fn commit(&mut self) -> Result<()> {
-        self.inner.commit().await
+        let commit = self.inner().commit();
+
+        commit.await?;
+
+        self.committed = true;
+
+        Ok(())
     }
 
     pub async fn rollback(&mut self) -> Result<()> {
-        self.inner.rollback().await
+        let rollback = self.inner().rollback();
+
+        self.clear_statement_cache();
+
+        rollback.await
+    }
+
+    /// Create a savepoint named `name`
+    pub async fn savepoint(&self, name: &str) -> Result<()> {
+        let savepoint = self.inner().savepoint(name);
+
+        savepoint.await
+    }
+
+    /// Release savepoint `name` and all savepoints created after it
+    pub async fn release(&self, name: &str) -> Result<()> {
+        let release = self.inner().release(name);
+
+        release.await
+    }
+
+    /// Undo the changes made after savepoint `name`, which stays active
+    pub async fn rollback_to(&self, name: &str) -> Result<()> {
+        let rollback_to = self.inner().rollback_to(name);
+
+        self.clear_statement_cache();
+
+        rollback_to.await
+    }
+
+    /// Cached statements may refer to the schema changes being rolled back,
+    /// the cache is kept if the transaction didn't change the schema.
+    fn clear_statement_cache(&self) {
+        if self.schema_changes.changed() {
+            self.cache.lock().unwrap().clear();
+        }
+    }
+
+    /// Start a nested transaction, backed by a savepoint of this transaction
+    pub async fn begin_nested(&mut self) -> Result<NestedTransaction<'_>> {
+        NestedTransaction::begin(self, 1).await
     }
 }
 
 impl Drop for Transaction {
     fn drop(&mut self) {
-        if let Some(conn) = self.conn.take() {
-            self.connection_pool
-                .lock()
-                .unwrap()
-                .insert(conn.id().to_owned(), conn);
+        // the driver rolls back the transaction when dropped before commit
+        if !self.committed {
+            self.clear_statement_cache();
+        }
+    }
+}
+
+/// Retry policy of [`crate::Database::transaction_with`]
+///
+/// The transaction is retried when it fails with a transient error (see
+/// [`crate::Error::is_transient`]), waiting an exponential backoff before each retry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, `0` disables retry.
+    pub max_retries: usize,
+    /// Wait before the first retry, doubled for each next retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait before a retry.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn never() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Wait before retry number `retry`, starting from 0
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let factor = 1u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Transaction nested in a [`Transaction`], see [`Transaction::begin_nested`]
+///
+/// Commit releases the savepoint, rollback undoes the changes made within the
+/// nested transaction only. Like [`Transaction`], it rolls back if dropped
+/// before commit.
+pub struct NestedTransaction<'a> {
+    tx: &'a Transaction,
+    name: String,
+    depth: usize,
+    finished: bool,
+}
+
+impl<'a> NestedTransaction<'a> {
+    async fn begin(tx: &'a Transaction, depth: usize) -> Result<Self> {
+        let name = format!("rdbc_nested_{}", depth);
+
+        tx.savepoint(&name).await?;
+
+        Ok(Self {
+            tx,
+            name,
+            depth,
+            finished: false,
+        })
+    }
+
+    pub async fn prepare(&self, query: &str) -> Result<Statement<'_>> {
+        self.tx.prepare(query).await
+    }
+
+    /// Runs every statement of `script` within this nested transaction
+    pub async fn execute_script(&self, script: &str) -> Result<driver::ExecuteResult> {
+        self.tx.execute_script(script).await
+    }
+
+    /// Release the savepoint, the nested transaction still rolls back on drop if it fails
+    pub async fn commit(&mut self) -> Result<()> {
+        self.tx.release(&self.name).await?;
+
+        self.finished = true;
+
+        Ok(())
+    }
+
+    pub async fn rollback(&mut self) -> Result<()> {
+        self.tx.rollback_to(&self.name).await?;
+        self.tx.release(&self.name).await?;
+
+        self.finished = true;
+
+        Ok(())
+    }
+
+    /// Create a savepoint named `name`
+    pub async fn savepoint(&self, name: &str) -> Result<()> {
+        self.tx.savepoint(name).await
+    }
+
+    /// Release savepoint `name` and all savepoints created after it
+    pub async fn release(&self, name: &str) -> Result<()> {
+        self.tx.release(name).await
+    }
+
+    /// Undo the changes made after savepoint `name`, which stays active
+    pub async fn rollback_to(&self, name: &str) -> Result<()> {
+        self.tx.rollback_to(name).await
+    }
+
+    /// Start a transaction nested in this one
+    pub async fn begin_nested(&mut self) -> Result<NestedTransaction<'_>> {
+        NestedTransaction::begin(self.tx, self.depth + 1).await
+    }
+}
+
+impl<'a> Drop for NestedTransaction<'a> {
+    fn drop(&mut self) {
+        // drivers submit these operations when they are called (see
+        // `driver::Transaction::rollback_to`), the results are not needed
+        if !self.finished {
+            let mut inner = self.tx.inner();
+
+            _ = inner.rollback_to(&self.name);
+            _ = inner.release(&self.name);
+
+            drop(inner);
+
+            self.tx.clear_statement_cache();
         }
     }
 }
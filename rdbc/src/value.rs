@@ -0,0 +1,266 @@
+use super::driver::Value;
+use super::error::{Error, Result};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ValueError {
+    #[error("expect {expected} value, found {found}")]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[error("value {0} out of range for type {1}")]
+    OutOfRange(String, &'static str),
+}
+
+impl ValueError {
+    fn type_mismatch(expected: &'static str, value: &Value) -> Error {
+        let found = match value {
+            Value::I64(_) => "integer",
+            Value::F64(_) => "float",
+            Value::String(_) => "string",
+            Value::Bytes(_) => "bytes",
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            #[cfg(feature = "chrono")]
+            Value::Date(_) => "date",
+            #[cfg(feature = "chrono")]
+            Value::Time(_) => "time",
+            #[cfg(feature = "chrono")]
+            Value::DateTime(_) => "datetime",
+            #[cfg(feature = "chrono")]
+            Value::Timestamp(_) => "timestamp",
+            #[cfg(feature = "decimal")]
+            Value::Decimal(_) => "decimal",
+            #[cfg(feature = "uuid")]
+            Value::Uuid(_) => "uuid",
+            #[cfg(feature = "json")]
+            Value::Json(_) => "json",
+        };
+
+        Error::from(ValueError::TypeMismatch { expected, found })
+    }
+
+    fn out_of_range(value: impl ToString, target: &'static str) -> Error {
+        Error::from(ValueError::OutOfRange(value.to_string(), target))
+    }
+}
+
+/// Conversion into a bind [`Value`], fails if the value can't be represented
+pub trait ToValue {
+    fn to_value(&self) -> Result<Value>;
+}
+
+/// Conversion from a column [`Value`], used by [`crate::Row::try_get`]
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Result<Value> {
+        Ok(self.clone())
+    }
+}
+
+macro_rules! declare_int_value {
+    ($($t:ty),*) => {
+        $(
+            impl ToValue for $t {
+                fn to_value(&self) -> Result<Value> {
+                    i64::try_from(*self)
+                        .map(Value::I64)
+                        .map_err(|_| ValueError::out_of_range(self, "i64"))
+                }
+            }
+
+            impl FromValue for $t {
+                fn from_value(value: &Value) -> Result<Self> {
+                    match value {
+                        Value::I64(v) => <$t>::try_from(*v)
+                            .map_err(|_| ValueError::out_of_range(v, stringify!($t))),
+                        _ => Err(ValueError::type_mismatch("integer", value)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+declare_int_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::F64(*self))
+    }
+}
+
+/// Largest magnitude of the integers an `f64` represents exactly, 2^53
+const F64_EXACT_INT: i64 = 1 << f64::MANTISSA_DIGITS;
+
+/// Also accepts integers which convert without rounding
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::F64(v) => Ok(*v),
+            Value::I64(v) if (-F64_EXACT_INT..=F64_EXACT_INT).contains(v) => Ok(*v as f64),
+            Value::I64(v) => Err(ValueError::out_of_range(v, "f64")),
+            _ => Err(ValueError::type_mismatch("float", value)),
+        }
+    }
+}
+
+impl ToValue for f32 {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::F64(*self as f64))
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &Value) -> Result<Self> {
+        let v = f64::from_value(value)?;
+
+        if v.is_finite() && v.abs() > f32::MAX as f64 {
+            return Err(ValueError::out_of_range(v, "f32"));
+        }
+
+        Ok(v as f32)
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::Bool(*self))
+    }
+}
+
+/// Also accepts integer `0` or `1`
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Bool(v) => Ok(*v),
+            Value::I64(0) => Ok(false),
+            Value::I64(1) => Ok(true),
+            Value::I64(v) => Err(ValueError::out_of_range(v, "bool")),
+            _ => Err(ValueError::type_mismatch("bool", value)),
+        }
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::String(self.to_owned()))
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::String(self.clone()))
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::String(v) => Ok(v.clone()),
+            _ => Err(ValueError::type_mismatch("string", value)),
+        }
+    }
+}
+
+impl ToValue for [u8] {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::Bytes(self.to_vec()))
+    }
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(&self) -> Result<Value> {
+        Ok(Value::Bytes(self.clone()))
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Bytes(v) => Ok(v.clone()),
+            _ => Err(ValueError::type_mismatch("bytes", value)),
+        }
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! declare_value {
+    ($variant:ident, $t:ty, $name:literal) => {
+        impl ToValue for $t {
+            fn to_value(&self) -> Result<Value> {
+                Ok(Value::$variant(self.clone()))
+            }
+        }
+
+        impl FromValue for $t {
+            fn from_value(value: &Value) -> Result<Self> {
+                match value {
+                    Value::$variant(v) => Ok(v.clone()),
+                    _ => Err(ValueError::type_mismatch($name, value)),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "chrono")]
+declare_value!(Date, chrono::NaiveDate, "date");
+#[cfg(feature = "chrono")]
+declare_value!(Time, chrono::NaiveTime, "time");
+#[cfg(feature = "chrono")]
+declare_value!(DateTime, chrono::NaiveDateTime, "datetime");
+#[cfg(feature = "chrono")]
+declare_value!(Timestamp, chrono::DateTime<chrono::Utc>, "timestamp");
+#[cfg(feature = "decimal")]
+declare_value!(Decimal, rust_decimal::Decimal, "decimal");
+#[cfg(feature = "uuid")]
+declare_value!(Uuid, uuid::Uuid, "uuid");
+#[cfg(feature = "json")]
+declare_value!(Json, serde_json::Value, "json");
+
+impl<T> ToValue for &T
+where
+    T: ToValue + ?Sized,
+{
+    fn to_value(&self) -> Result<Value> {
+        (**self).to_value()
+    }
+}
+
+/// `None` maps to `NULL`
+impl<T> ToValue for Option<T>
+where
+    T: ToValue,
+{
+    fn to_value(&self) -> Result<Value> {
+        match self {
+            Some(v) => v.to_value(),
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+/// `NULL` maps to `None`
+impl<T> FromValue for Option<T>
+where
+    T: FromValue,
+{
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
@@ -1,11 +1,22 @@
 use std::{
     sync::{Arc, Mutex},
     task::Poll,
+    time::{Duration, Instant},
 };
 
+/// Start of an operation deferred until its future is polled, see [`Waker::admit_with`]
+pub(crate) trait Admission<Output>: Send {
+    /// `Ready(None)` once the operation is started, `Ready(Some(output))` if it can't start.
+    ///
+    /// Called with the [`Waker`] locked, the output of a started operation is only readied
+    /// after this returns.
+    fn poll_admit(&mut self, waker: &std::task::Waker) -> Poll<Option<Output>>;
+}
+
 pub struct Waker<Output> {
     pub waker: Option<std::task::Waker>,
     pub output: Option<Output>,
+    admission: Option<Box<dyn Admission<Output>>>,
 }
 
 impl<Output> Waker<Output> {
@@ -14,6 +25,19 @@ impl<Output> Waker<Output> {
             return Poll::Ready(output);
         }
 
+        if let Some(admission) = &mut self.admission {
+            match admission.poll_admit(&waker) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(output) => {
+                    self.admission = None;
+
+                    if let Some(output) = output {
+                        return Poll::Ready(output);
+                    }
+                }
+            }
+        }
+
         self.waker = Some(waker);
 
         Poll::Pending
@@ -27,6 +51,13 @@ impl<Output> Waker<Output> {
             waker.wake_by_ref();
         }
     }
+
+    /// Defers the operation until `admission` admits it, polled before the output.
+    ///
+    /// Dropping the waker drops `admission` too.
+    pub(crate) fn admit_with(&mut self, admission: impl Admission<Output> + 'static) {
+        self.admission = Some(Box::new(admission));
+    }
 }
 
 /// Waker shared between threads
@@ -37,6 +68,7 @@ pub fn new_shared_waker<Output>() -> SharedWaker<Output> {
     Arc::new(Mutex::new(Waker::<Output> {
         waker: Default::default(),
         output: None,
+        admission: None,
     }))
 }
 
@@ -50,17 +82,17 @@ impl<Output> WakableFuture<Output> {
     pub fn new() -> (Self, SharedWaker<Output>) {
         let waker = new_shared_waker();
 
-        return (
+        (
             Self {
                 waker: waker.clone(),
             },
             waker,
-        );
+        )
     }
 
     pub fn map<MOutput>(
         &self,
-        f: impl FnOnce(Output) -> MOutput + 'static,
+        f: impl FnOnce(Output) -> MOutput + Send + 'static,
     ) -> WakableMapFuture<MOutput, Output> {
         WakableMapFuture {
             waker: self.waker.clone(),
@@ -81,10 +113,31 @@ impl<Output> std::future::Future for WakableFuture<Output> {
     }
 }
 
+/// Returns a future that is ready after `duration`, without depending on an async runtime.
+///
+/// Runs on the shared [`crate::timer`] thread.
+pub(crate) fn delay(duration: Duration) -> WakableFuture<()> {
+    let (fut, waker) = WakableFuture::new();
+
+    let timer = waker.clone();
+
+    let scheduled = crate::timer::schedule(Instant::now() + duration, move || {
+        timer.lock().unwrap().ready(());
+    });
+
+    if let Err(err) = scheduled {
+        log::error!("schedule delay failed: {}", err);
+
+        waker.lock().unwrap().ready(());
+    }
+
+    fut
+}
+
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct WakableMapFuture<MOutput, Output> {
     pub waker: SharedWaker<Output>,
-    map_f: Option<Box<dyn FnOnce(Output) -> MOutput>>,
+    map_f: Option<Box<dyn FnOnce(Output) -> MOutput + Send>>,
 }
 
 impl<MOutput, Output> WakableMapFuture<MOutput, Output> {
@@ -92,13 +145,13 @@ impl<MOutput, Output> WakableMapFuture<MOutput, Output> {
     pub fn new() -> (Self, SharedWaker<Output>) {
         let waker = new_shared_waker();
 
-        return (
+        (
             Self {
                 waker: waker.clone(),
                 map_f: None,
             },
             waker,
-        );
+        )
     }
 }
 
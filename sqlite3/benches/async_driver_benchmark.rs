@@ -0,0 +1,102 @@
+use criterion::Criterion;
+use criterion::{criterion_group, criterion_main};
+
+// This is a struct that tells Criterion.rs to use the "futures" crate's current-thread executor
+use criterion::async_executor::FuturesExecutor;
+
+use std::time::Duration;
+use std::{
+    fs::{create_dir_all, remove_file},
+    path::PathBuf,
+};
+
+use rdbc_sqlite3::async_driver::AsyncDriver;
+
+/// Concurrent readers of each benchmark iteration, also the pool size
+const READERS: usize = 4;
+
+/// Opens a WAL mode database `.test/{name}.db` with 1000 rows through `driver`
+async fn prepare_benchmark(name: &str, driver: AsyncDriver) -> rdbc::Database {
+    _ = rdbc::register_driver(name, driver);
+
+    let path: PathBuf = ".test".into();
+
+    create_dir_all(&path).unwrap();
+
+    let path = path.join(format!("{}.db", name));
+
+    for suffix in ["", "-wal", "-shm"] {
+        let path = PathBuf::from(format!("{}{}", path.to_string_lossy(), suffix));
+
+        if path.exists() {
+            remove_file(&path).unwrap();
+        }
+    }
+
+    let path = format!("file:{}", path.to_string_lossy());
+
+    let mut db = rdbc::open_with(
+        name,
+        &path,
+        rdbc::PoolOptions {
+            max_open_connections: READERS,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    db.execute_script(
+        "PRAGMA journal_mode = WAL;
+        CREATE TABLE t(x INTEGER PRIMARY KEY ASC, y TEXT, z NUMERIC);
+        INSERT INTO t(y, z)
+            WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c LIMIT 1000)
+            SELECT 'row ' || x, x * 0.5 FROM c;",
+    )
+    .await
+    .unwrap();
+
+    db
+}
+
+/// A read query that takes a few milliseconds
+async fn select_join(mut db: rdbc::Database) {
+    let mut stmt = db
+        .prepare("SELECT count(*) FROM t a, t b WHERE a.x % 97 = b.x % 89;")
+        .await
+        .unwrap();
+
+    let mut rows = stmt.query(()).await.unwrap();
+
+    assert!(rows.next().await.unwrap());
+}
+
+/// [`READERS`] queries at the same time, each on its own pooled connection
+async fn concurrent_readers(db: rdbc::Database) {
+    futures::future::join_all((0..READERS).map(|_| select_join(db.clone()))).await;
+}
+
+/// Compare the single worker thread with the default worker pool, one worker per CPU
+fn workers_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("async-driver-workers");
+
+    group.measurement_time(Duration::from_secs(10));
+
+    for (name, driver) in [
+        ("single-thread", AsyncDriver::with_workers(1)),
+        ("worker-pool", AsyncDriver::new()),
+    ] {
+        let db =
+            async_std::task::block_on(prepare_benchmark(&format!("bench_async_{}", name), driver));
+
+        group.bench_function(format!("concurrent readers {}", name), |b| {
+            b.to_async(FuturesExecutor)
+                .iter(|| concurrent_readers(db.clone()));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, workers_benchmark);
+
+criterion_main!(benches);
@@ -6,7 +6,7 @@ use criterion::async_executor::FuturesExecutor;
 
 use std::time::Duration;
 use std::{
-    fs::{create_dir_all, remove_dir_all},
+    fs::{create_dir_all, remove_file},
     path::PathBuf,
 };
 
@@ -15,22 +15,22 @@ use rdbc_sqlite3::*;
 use rdbc::{Placeholder, Value};
 
 #[allow(dead_code)]
-async fn prepare_benchmark() -> rdbc::Database {
+async fn prepare_benchmark(name: &str, options: rdbc::PoolOptions) -> rdbc::Database {
     _ = register_sqlite3();
 
     let path: PathBuf = ".test".into();
 
-    if path.exists() {
-        remove_dir_all(path.to_owned()).unwrap();
-    }
+    create_dir_all(&path).unwrap();
 
-    create_dir_all(path.to_owned()).unwrap();
+    let path = path.join(format!("{}.db", name));
 
-    let path = path.join("test.db");
+    if path.exists() {
+        remove_file(&path).unwrap();
+    }
 
     let path = format!("file:{}", path.to_string_lossy());
 
-    let mut db = rdbc::open("sqlite3", &path).unwrap();
+    let mut db = rdbc::open_with("sqlite3", &path, options).unwrap();
 
     // let mut db = rdbc::open("sqlite3", "file:memdb_commit?mode=memory&cache=shared").unwrap();
 
@@ -62,8 +62,29 @@ async fn insert_one_row(mut db: rdbc::Database) {
         .unwrap();
 }
 
+/// Prepare and run a query several times in one transaction, which keeps its
+/// connection, so the prepare time is not hidden by the pool acquire and release.
+async fn select_rows(mut db: rdbc::Database) {
+    let mut tx = db.begin().await.unwrap();
+
+    for x in 1..=10 {
+        let mut stmt = tx
+            .prepare("SELECT x, y, z FROM t WHERE x = ?;")
+            .await
+            .unwrap();
+
+        let mut rows = stmt.query(rdbc::params![x]).await.unwrap();
+
+        assert!(rows.next().await.unwrap());
+    }
+
+    tx.commit().await.unwrap();
+}
+
 fn insert_benchmark(c: &mut Criterion) {
-    let db = async_std::task::block_on(async { prepare_benchmark().await });
+    let db = async_std::task::block_on(async {
+        prepare_benchmark("bench_insert", Default::default()).await
+    });
 
     let mut group = c.benchmark_group("example-cuad");
 
@@ -77,6 +98,38 @@ fn insert_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, insert_benchmark);
+/// Compare [`select_rows`] with and without the prepared statement cache
+fn statement_cache_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("example-cuad-statement-cache");
+
+    group.measurement_time(Duration::from_secs(10));
+
+    for (name, capacity) in [("cached", 100), ("uncached", 0)] {
+        let db = async_std::task::block_on(async {
+            let db = prepare_benchmark(
+                &format!("bench_select_{}", name),
+                rdbc::PoolOptions {
+                    statement_cache_capacity: capacity,
+                    ..Default::default()
+                },
+            )
+            .await;
+
+            for _ in 0..10 {
+                insert_one_row(db.clone()).await;
+            }
+
+            db
+        });
+
+        group.bench_function(format!("select benchmark {}", name), |b| {
+            b.to_async(FuturesExecutor).iter(|| select_rows(db.clone()));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, insert_benchmark, statement_cache_benchmark);
 
 criterion_main!(benches);
@@ -1,447 +1,213 @@
+use super::options::SqliteOptions;
 use super::sqlite3_rs;
-use rdbc::driver;
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender},
-};
-
-#[allow(dead_code)]
+use rdbc::driver::{self, threaded};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs [`sqlite3_rs`] on a pool of worker threads, see [`threaded::ThreadedDriver`]
+///
+/// Each connection stays on one worker, so a slow query only delays the connections
+/// sharing its worker, and readers of a WAL mode database run in parallel.
 pub struct AsyncDriver {
-    sender: Sender<driver::Task>,
+    inner: threaded::ThreadedDriver,
 }
 
-fn fetch_object<'a, Obj, Output>(
-    waker: &rdbc::SharedWaker<anyhow::Result<Output>>,
-    map: &'a mut HashMap<String, Obj>,
-    id: &str,
-) -> Option<&'a mut Obj> {
-    let obj = map.get_mut(id);
+impl AsyncDriver {
+    /// Default [`threaded::ThreadedOptions`], one worker per available CPU
+    pub fn new() -> Self {
+        Self::with_options(Default::default())
+    }
 
-    if obj.is_none() {
-        waker
-            .lock()
-            .unwrap()
-            .ready(Err(anyhow::anyhow!("sqlite3 resource not found {}", id)));
+    /// Use `workers` worker threads, `1` runs every connection on the same thread.
+    pub fn with_workers(workers: usize) -> Self {
+        Self::with_options(threaded::ThreadedOptions {
+            workers,
+            ..Default::default()
+        })
     }
 
-    obj
-}
+    /// Configure the worker threads and their task queue capacity
+    pub fn with_options(options: threaded::ThreadedOptions) -> Self {
+        Self::with_sqlite_options(Default::default(), options)
+    }
 
-impl AsyncDriver {
-    pub fn new() -> Self {
-        let (sender, receiver) = channel();
-
-        let execute_loop_sender = sender.clone();
-
-        std::thread::spawn(move || Self::execute_loop(execute_loop_sender, receiver));
-
-        Self { sender }
-    }
-
-    fn execute_loop(
-        sender: Sender<driver::Task>,
-        receiver: Receiver<driver::Task>,
-    ) -> anyhow::Result<()> {
-        let mut cnns = HashMap::<String, sqlite3_rs::Connection>::new();
-        let mut stmts = HashMap::<String, sqlite3_rs::Statement>::new();
-        let mut txs = HashMap::<String, sqlite3_rs::Transaction>::new();
-        let mut results = HashMap::<String, sqlite3_rs::Rows>::new();
-
-        loop {
-            match receiver.recv()? {
-                driver::Task::Begin(id, waker) => {
-                    if let Some(conn) = fetch_object(&waker, &mut cnns, &id) {
-                        waker.lock().unwrap().ready(conn.begin().map(|tx| {
-                            {
-                                let id = tx.id.clone();
-
-                                txs.insert(tx.id.clone(), tx);
-
-                                AsyncTransaction {
-                                    sender: sender.clone(),
-                                    id,
-                                }
-                            }
-                            .into()
-                        }));
-                    }
-                }
-
-                driver::Task::CloseConnection(id) => {
-                    cnns.remove(&id);
-                }
-
-                driver::Task::Prepare(id, query, waker) => {
-                    if let Some(conn) = fetch_object(&waker, &mut cnns, &id) {
-                        waker.lock().unwrap().ready(conn.prepare(&query).map(|obj| {
-                            {
-                                let id = obj.id.clone();
-
-                                let inputs = obj.num_input();
-
-                                stmts.insert(obj.id.clone(), obj);
-
-                                AsyncStatement {
-                                    sender: sender.clone(),
-                                    id,
-                                    inputs,
-                                }
-                            }
-                            .into()
-                        }));
-                    }
-                }
-
-                driver::Task::Open(url, waker) => match sqlite3_rs::Connection::open(&url) {
-                    Ok(conn) => {
-                        let id = conn.id.clone();
-
-                        cnns.insert(id.clone(), conn);
-
-                        waker.lock().unwrap().ready(Ok(AsyncConnection {
-                            id,
-                            sender: sender.clone(),
-                        }
-                        .into()));
-                    }
-                    Err(err) => {
-                        waker.lock().unwrap().ready(Err(err));
-                    }
-                },
-
-                driver::Task::Execute(id, args, waker) => {
-                    if let Some(stmt) = fetch_object(&waker, &mut stmts, &id) {
-                        waker.lock().unwrap().ready(stmt.execute(args));
-                    }
-                }
-
-                driver::Task::Query(id, args, waker) => {
-                    if let Some(stmt) = fetch_object(&waker, &mut stmts, &id) {
-                        waker.lock().unwrap().ready(stmt.query(args).map(|obj| {
-                            {
-                                let id = obj.id.clone();
-
-                                results.insert(obj.id.clone(), obj);
-
-                                AsyncRows {
-                                    sender: sender.clone(),
-                                    id,
-                                }
-                            }
-                            .into()
-                        }));
-                    }
-                }
-
-                driver::Task::Columns(id, waker) => {
-                    if let Some(rows) = fetch_object(&waker, &mut results, &id) {
-                        waker
-                            .lock()
-                            .unwrap()
-                            .ready(rows.colunms().map(|c| c.clone()));
-                    }
-                }
-
-                driver::Task::RowsNext(id, waker) => {
-                    if let Some(rows) = fetch_object(&waker, &mut results, &id) {
-                        waker.lock().unwrap().ready(rows.next());
-                    }
-                }
-
-                driver::Task::RowsGet(id, pos, col_type, waker) => {
-                    if let Some(rows) = fetch_object(&waker, &mut results, &id) {
-                        waker.lock().unwrap().ready(rows.get(pos, col_type));
-                    }
-                }
-
-                driver::Task::TxPrepare(id, query, waker) => {
-                    if let Some(tx) = fetch_object(&waker, &mut txs, &id) {
-                        waker.lock().unwrap().ready(tx.prepare(&query).map(|obj| {
-                            {
-                                let id = obj.id.clone();
-
-                                let inputs = obj.num_input();
-
-                                stmts.insert(obj.id.clone(), obj);
-
-                                AsyncStatement {
-                                    sender: sender.clone(),
-                                    id,
-                                    inputs,
-                                }
-                            }
-                            .into()
-                        }));
-                    }
-                }
-
-                driver::Task::Commit(id, waker) => {
-                    if let Some(tx) = fetch_object(&waker, &mut txs, &id) {
-                        waker.lock().unwrap().ready(tx.commit());
-                    }
-                }
-
-                driver::Task::Rollback(id, waker) => {
-                    if let Some(tx) = fetch_object(&waker, &mut txs, &id) {
-                        waker.lock().unwrap().ready(tx.rollback());
-                    }
-                }
-
-                driver::Task::CloseTx(id) => {
-                    txs.remove(&id);
-                }
-
-                driver::Task::CloseStmt(id) => {
-                    stmts.remove(&id);
-                }
-
-                driver::Task::CloseRows(id) => {
-                    results.remove(&id);
-                }
-            }
+    /// Open the connections with `sqlite`, see [`AsyncDriver::with_options`]
+    pub fn with_sqlite_options(sqlite: SqliteOptions, options: threaded::ThreadedOptions) -> Self {
+        Self {
+            inner: threaded::ThreadedDriver::with_options(
+                BlockingDriver { options: sqlite },
+                options,
+            ),
         }
     }
+
+    /// See [`threaded::ThreadedDriver::queue_status`]
+    pub fn queue_status(&self) -> threaded::QueueStatus {
+        self.inner.queue_status()
+    }
+
+    /// See [`threaded::ThreadedDriver::queue_monitor`]
+    pub fn queue_monitor(&self) -> threaded::QueueMonitor {
+        self.inner.queue_monitor()
+    }
 }
 
-fn send_task<Output>(
-    sender: &mut Sender<driver::Task>,
-    waker: rdbc::SharedWaker<anyhow::Result<Output>>,
-    task: driver::Task,
-) {
-    if let Err(err) = sender.send(task) {
-        waker.lock().unwrap().ready(Err(anyhow::Error::new(err)));
+impl Default for AsyncDriver {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl driver::Driver for AsyncDriver {
     fn open(&mut self, name: &str) -> driver::Connector {
-        let (fut, waker) = driver::Connector::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Open(name.to_owned(), waker),
-        );
+        driver::Driver::open(&mut self.inner, name)
+    }
 
-        fut
+    /// See [`threaded::ThreadedDriver::shutdown`]
+    fn shutdown(&mut self) -> rdbc::Result<()> {
+        self.inner.shutdown()
     }
 }
 
-struct AsyncConnection {
-    sender: Sender<driver::Task>,
-    id: String,
+#[derive(Clone)]
+struct BlockingDriver {
+    options: SqliteOptions,
 }
 
-impl Into<Box<dyn driver::Connection>> for AsyncConnection {
-    fn into(self) -> Box<dyn driver::Connection> {
-        Box::new(self)
-    }
-}
+impl threaded::BlockingDriver for BlockingDriver {
+    type Connection = sqlite3_rs::Connection;
 
-impl Drop for AsyncConnection {
-    fn drop(&mut self) {
-        _ = self
-            .sender
-            .send(driver::Task::CloseConnection(self.id.clone()));
+    fn open(&mut self, name: &str) -> rdbc::Result<Self::Connection> {
+        sqlite3_rs::Connection::open_with(name, &self.options)
     }
 }
 
-impl driver::Connection for AsyncConnection {
-    fn begin(&mut self) -> driver::Begin {
-        let (fut, waker) = driver::Begin::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Begin(self.id.clone(), waker),
-        );
-
-        fut
-    }
+impl threaded::BlockingConnection for sqlite3_rs::Connection {
+    type Statement = sqlite3_rs::Statement;
+    type Transaction = sqlite3_rs::Transaction;
 
     fn id(&self) -> &str {
         &self.id
     }
 
-    fn is_valid(&self) -> bool {
-        true
+    fn prepare(&mut self, query: &str) -> rdbc::Result<Self::Statement> {
+        sqlite3_rs::Connection::prepare(self, query)
     }
 
-    fn prepare(&mut self, query: &str) -> driver::Prepare {
-        let (fut, waker) = driver::Prepare::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Prepare(self.id.clone(), query.to_owned(), waker),
-        );
-
-        fut
+    fn execute_script(&mut self, script: &str) -> rdbc::Result<driver::ExecuteResult> {
+        sqlite3_rs::Connection::execute_script(self, script)
     }
-}
 
-struct AsyncTransaction {
-    sender: Sender<driver::Task>,
-    id: String,
-}
+    fn begin(&mut self, options: driver::TransactionOptions) -> rdbc::Result<Self::Transaction> {
+        sqlite3_rs::Connection::begin(self, options)
+    }
 
-impl Drop for AsyncTransaction {
-    fn drop(&mut self) {
-        _ = self.sender.send(driver::Task::CloseTx(self.id.clone()));
+    fn ping(&mut self) -> rdbc::Result<()> {
+        sqlite3_rs::Connection::ping(self)
     }
-}
 
-impl Into<Box<dyn driver::Transaction>> for AsyncTransaction {
-    fn into(self) -> Box<dyn driver::Transaction> {
-        Box::new(self)
+    fn reset(&mut self) -> rdbc::Result<bool> {
+        sqlite3_rs::Connection::reset(self)
     }
 }
 
-impl driver::Transaction for AsyncTransaction {
-    fn commit(&mut self) -> driver::Commit {
-        let (fut, waker) = driver::Commit::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Commit(self.id.clone(), waker),
-        );
+impl threaded::BlockingTransaction for sqlite3_rs::Transaction {
+    type Statement = sqlite3_rs::Statement;
 
-        fut
+    fn prepare(&mut self, query: &str) -> rdbc::Result<Self::Statement> {
+        sqlite3_rs::Transaction::prepare(self, query)
     }
 
-    fn prepare(&mut self, query: &str) -> driver::Prepare {
-        let (fut, waker) = driver::Prepare::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::TxPrepare(self.id.clone(), query.to_owned(), waker),
-        );
-
-        fut
+    fn execute_script(&mut self, script: &str) -> rdbc::Result<driver::ExecuteResult> {
+        sqlite3_rs::Transaction::execute_script(self, script)
     }
 
-    fn rollback(&mut self) -> driver::Rollback {
-        let (fut, waker) = driver::Rollback::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Rollback(self.id.clone(), waker),
-        );
+    fn commit(&mut self) -> rdbc::Result<()> {
+        sqlite3_rs::Transaction::commit(self)
+    }
 
-        fut
+    fn rollback(&mut self) -> rdbc::Result<()> {
+        sqlite3_rs::Transaction::rollback(self)
     }
-}
 
-struct AsyncStatement {
-    sender: Sender<driver::Task>,
-    id: String,
-    inputs: Option<u32>,
-}
+    fn savepoint(&mut self, name: &str) -> rdbc::Result<()> {
+        sqlite3_rs::Transaction::savepoint(self, name)
+    }
 
-impl Drop for AsyncStatement {
-    fn drop(&mut self) {
-        _ = self.sender.send(driver::Task::CloseStmt(self.id.clone()));
+    fn release(&mut self, name: &str) -> rdbc::Result<()> {
+        sqlite3_rs::Transaction::release(self, name)
     }
-}
 
-unsafe impl Send for AsyncStatement {}
+    fn rollback_to(&mut self, name: &str) -> rdbc::Result<()> {
+        sqlite3_rs::Transaction::rollback_to(self, name)
+    }
 
-impl Into<Box<dyn driver::Statement>> for AsyncStatement {
-    fn into(self) -> Box<dyn driver::Statement> {
-        Box::new(self)
+    fn schema_changes(&self) -> Arc<dyn driver::SchemaChanges> {
+        sqlite3_rs::Transaction::schema_changes(self)
     }
 }
 
-impl driver::Statement for AsyncStatement {
-    fn execute(&mut self, args: Vec<rdbc::Arg>) -> driver::Execute {
-        let (fut, waker) = driver::Execute::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Execute(self.id.clone(), args, waker),
-        );
-
-        fut
-    }
+impl threaded::BlockingStatement for sqlite3_rs::Statement {
+    type Rows = sqlite3_rs::Rows;
 
     fn num_input(&self) -> Option<u32> {
-        self.inputs
+        sqlite3_rs::Statement::num_input(self)
     }
 
-    fn query(&mut self, args: Vec<rdbc::Arg>) -> driver::Query {
-        let (fut, waker) = driver::Query::new();
+    fn execute(&mut self, args: Vec<rdbc::Arg>) -> rdbc::Result<driver::ExecuteResult> {
+        sqlite3_rs::Statement::execute(self, args)
+    }
 
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Query(self.id.clone(), args, waker),
-        );
+    fn execute_batch(
+        &mut self,
+        batch: Vec<Vec<rdbc::Arg>>,
+    ) -> rdbc::Result<Vec<driver::ExecuteResult>> {
+        sqlite3_rs::Statement::execute_batch(self, batch)
+    }
 
-        fut
+    fn query(&mut self, args: Vec<rdbc::Arg>) -> rdbc::Result<Self::Rows> {
+        sqlite3_rs::Statement::query(self, args)
     }
-}
 
-struct AsyncRows {
-    sender: Sender<driver::Task>,
-    id: String,
-}
+    fn execute_returning(
+        &mut self,
+        args: Vec<rdbc::Arg>,
+    ) -> rdbc::Result<(driver::ExecuteResult, Self::Rows)> {
+        sqlite3_rs::Statement::execute_returning(self, args)
+    }
 
-impl Drop for AsyncRows {
-    fn drop(&mut self) {
-        _ = self.sender.send(driver::Task::CloseRows(self.id.clone()));
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        sqlite3_rs::Statement::set_timeout(self, timeout)
     }
-}
 
-impl Into<Box<dyn driver::Rows>> for AsyncRows {
-    fn into(self) -> Box<dyn driver::Rows> {
-        Box::new(self)
+    /// Interrupts the database directly, a task would wait for the running one
+    fn cancel_handle(&self) -> Arc<dyn driver::CancelHandle> {
+        Arc::new(self.interrupt_handle())
     }
 }
 
-unsafe impl Send for AsyncRows {}
-
-impl driver::Rows for AsyncRows {
-    fn colunms(&mut self) -> driver::Columns {
-        let (fut, waker) = driver::Columns::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::Columns(self.id.clone(), waker),
-        );
+impl threaded::BlockingRows for sqlite3_rs::Rows {
+    fn colunms(&mut self) -> rdbc::Result<Vec<driver::ColumnMetaData>> {
+        sqlite3_rs::Rows::colunms(self).cloned()
+    }
 
-        fut
+    fn next(&mut self) -> rdbc::Result<bool> {
+        sqlite3_rs::Rows::next(self)
     }
 
     fn get(
         &mut self,
         pos: driver::Placeholder,
         column_type: driver::ColumnType,
-    ) -> driver::RowsGet {
-        let (fut, waker) = driver::RowsGet::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::RowsGet(self.id.clone(), pos, column_type, waker),
-        );
-
-        fut
+    ) -> rdbc::Result<rdbc::Value> {
+        sqlite3_rs::Rows::get(self, pos, column_type)
     }
 
-    fn next(&mut self) -> driver::RowsNext {
-        let (fut, waker) = driver::RowsNext::new();
-
-        send_task(
-            &mut self.sender,
-            waker.clone(),
-            driver::Task::RowsNext(self.id.clone(), waker),
-        );
+    fn get_row(&mut self) -> rdbc::Result<Vec<rdbc::Value>> {
+        sqlite3_rs::Rows::get_row(self)
+    }
 
-        fut
+    fn next_batch(&mut self, n: usize) -> rdbc::Result<Vec<Vec<rdbc::Value>>> {
+        sqlite3_rs::Rows::next_batch(self, n)
     }
 }
@@ -1,5 +1,8 @@
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
 use std::os::raw::{c_char, c_int};
 
+use rdbc::ErrorKind;
 use sqlite3_sys::*;
 use thiserror::Error;
 
@@ -22,22 +25,114 @@ pub enum Sqlite3Error {
 
     #[error("Sqlite3 get column by name {0}, not found")]
     UnknownColumn(String),
+
+    #[error("Sqlite3 database file has been moved or deleted")]
+    DatabaseMoved,
+
+    #[error("Sqlite3 unsupported value type {0}")]
+    UnsupportedType(String),
+
+    #[error("Sqlite3 decode {0:?} as {1:?} failed")]
+    DecodeError(rdbc::Value, rdbc::ColumnType),
+
+    #[error("Sqlite3 invalid input sql {0}")]
+    InvalidSql(String),
+
+    #[error("Sqlite3 prepare SQL with several statements {0}, use execute_script")]
+    MultipleStatements(String),
+
+    #[error("Sqlite3 statement timeout after {0:?}")]
+    Timeout(std::time::Duration),
+
+    #[error(transparent)]
+    Nul(#[from] std::ffi::NulError),
+}
+
+impl Sqlite3Error {
+    /// The portable [`ErrorKind`] of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            // extended result codes share the primary code in the lower 8 bits
+            Sqlite3Error::NativeError(code, _) => match code & 0xff {
+                SQLITE_CONSTRAINT => ErrorKind::Constraint,
+                SQLITE_BUSY | SQLITE_LOCKED => ErrorKind::Busy,
+                SQLITE_ERROR if is_sql_error(self.message()) => ErrorKind::Syntax,
+                SQLITE_CANTOPEN | SQLITE_NOTADB | SQLITE_IOERR | SQLITE_PERM => {
+                    ErrorKind::Connection
+                }
+                SQLITE_MISMATCH | SQLITE_RANGE | SQLITE_TOOBIG => ErrorKind::TypeMismatch,
+                SQLITE_INTERRUPT => ErrorKind::Cancelled,
+                _ => ErrorKind::Other,
+            },
+            Sqlite3Error::DatabaseMoved => ErrorKind::Connection,
+            Sqlite3Error::UnsupportedType(_) | Sqlite3Error::DecodeError(..) => {
+                ErrorKind::TypeMismatch
+            }
+            Sqlite3Error::InvalidSql(_) | Sqlite3Error::MultipleStatements(_) => ErrorKind::Syntax,
+            Sqlite3Error::Timeout(_) => ErrorKind::Timeout,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// The sqlite3 error message of a native error, without the SQL it refers to
+    fn message(&self) -> &str {
+        match self {
+            Sqlite3Error::NativeError(_, message) => message
+                .split_once(WITH_SQL)
+                .map_or(message.as_str(), |(message, _)| message),
+            _ => "",
+        }
+    }
+
+    /// The sqlite3 (extended) result code of a native error
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            Sqlite3Error::NativeError(code, _) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+/// Separates the sqlite3 error message from the SQL, see [`error_with_sql`]
+const WITH_SQL: &str = ", with SQL ";
+
+/// Messages of the `SQLITE_ERROR`s reporting an invalid SQL text or an unknown
+/// table, column, function or savepoint, other `SQLITE_ERROR`s are [`ErrorKind::Other`].
+const SQL_ERROR_MESSAGES: &[&str] = &[
+    "near ",
+    "incomplete input",
+    "unrecognized token",
+    "no such ",
+    "ambiguous column name",
+    "wrong number of arguments",
+];
+
+fn is_sql_error(message: &str) -> bool {
+    SQL_ERROR_MESSAGES
+        .iter()
+        .any(|prefix| message.starts_with(prefix))
+}
+
+impl From<Sqlite3Error> for rdbc::Error {
+    fn from(err: Sqlite3Error) -> Self {
+        rdbc::Error::new(err.kind(), err)
+    }
 }
 
-pub fn native_error(code: i32, message: String) -> anyhow::Error {
-    anyhow::Error::new(Sqlite3Error::NativeError(code, message))
+pub fn native_error(code: i32, message: String) -> rdbc::Error {
+    Sqlite3Error::NativeError(code, message).into()
 }
 
-pub fn db_native_error(db: *mut sqlite3, code: c_int) -> anyhow::Error {
+pub fn db_native_error(db: *mut sqlite3, code: c_int) -> rdbc::Error {
     let errmsg = unsafe { errmsg_to_string(sqlite3_errmsg(db)) };
 
     native_error(code, errmsg)
 }
 
-pub fn error_with_sql(db: *mut sqlite3, code: c_int, sql: &str) -> anyhow::Error {
+pub fn error_with_sql(db: *mut sqlite3, code: c_int, sql: &str) -> rdbc::Error {
     let errmsg = unsafe { errmsg_to_string(sqlite3_errmsg(db)) };
 
-    native_error(code, format!("{}, with SQL {}", errmsg, sql))
+    native_error(code, format!("{}{}{}", errmsg, WITH_SQL, sql))
 }
 
 unsafe fn errmsg_to_string(errmsg: *const c_char) -> String {
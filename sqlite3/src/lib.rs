@@ -1,21 +1,37 @@
 pub mod error;
 
+pub mod options;
+
+pub mod script;
+
 pub mod sqlite3_rs;
 
 pub mod sync_driver;
 
+pub mod types;
+
 #[cfg(feature = "async-sqlite3")]
 pub mod async_driver;
 
+pub use options::SqliteOptions;
+
 // #[cfg_attr(feature = "async-sqlite3", path = "./sync_driver.rs")]
 // pub mod driver;
 
-pub fn register_sqlite3() -> anyhow::Result<()> {
+pub fn register_sqlite3() -> rdbc::Result<()> {
+    register_sqlite3_with("sqlite3", SqliteOptions::default())
+}
+
+/// Register the sqlite3 driver as `name`, opening its connections with `options`
+pub fn register_sqlite3_with(name: &str, options: SqliteOptions) -> rdbc::Result<()> {
     #[cfg(feature = "async-sqlite3")]
-    return rdbc::register_driver("sqlite3", async_driver::AsyncDriver::new());
+    return rdbc::register_driver(
+        name,
+        async_driver::AsyncDriver::with_sqlite_options(options, Default::default()),
+    );
 
     #[cfg(not(feature = "async-sqlite3"))]
-    return rdbc::register_driver("sqlite3", sync_driver::SyncDriver {});
+    return rdbc::register_driver(name, sync_driver::SyncDriver { options });
 }
 
 #[cfg(test)]
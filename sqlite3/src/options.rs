@@ -0,0 +1,61 @@
+use std::os::raw::c_int;
+
+use sqlite3_sys::*;
+
+/// Url scheme of rdbc sqlite3 urls, `sqlite3://{path}?{params}`
+pub const URL_SCHEME: &str = "sqlite3://";
+
+/// Options of the connections opened by the sqlite3 driver, see [`crate::register_sqlite3_with`]
+///
+/// A database url can still restrict them with the sqlite3 uri parameters, e.g.
+/// `sqlite3://replica.db?mode=ro&vfs=unix-dotfile`.
+///
+/// Connections are always opened in serialized mode (`SQLITE_OPEN_FULLMUTEX`) and the
+/// threading mode can't be configured: the connection, statement and rows handles are
+/// `Send` and `Sync`, their statements and rows may be used and dropped on other threads,
+/// which is only sound if sqlite3 serializes the calls made on one connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteOptions {
+    /// Open the database read-only, writes fail with `SQLITE_READONLY`.
+    pub read_only: bool,
+    /// Create the database if it doesn't exist, ignored by read-only connections.
+    pub create: bool,
+    /// Name of the sqlite3 VFS, [`None`] uses the default VFS.
+    pub vfs: Option<String>,
+}
+
+impl Default for SqliteOptions {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            create: true,
+            vfs: None,
+        }
+    }
+}
+
+impl SqliteOptions {
+    /// `sqlite3_open_v2` flags
+    pub fn flags(&self) -> c_int {
+        let mode = if self.read_only {
+            SQLITE_OPEN_READONLY
+        } else if self.create {
+            SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE
+        } else {
+            SQLITE_OPEN_READWRITE
+        };
+
+        SQLITE_OPEN_URI | SQLITE_OPEN_FULLMUTEX | mode
+    }
+}
+
+/// Converts a `sqlite3://` url to a sqlite3 `file:` uri, other names are returned unchanged.
+///
+/// sqlite3 parses the uri parameters, e.g. `mode`, `vfs`, `cache` or `immutable`,
+/// which override the [`SqliteOptions`] as long as they are not less restrictive.
+pub fn sqlite_uri(name: &str) -> String {
+    match name.strip_prefix(URL_SCHEME) {
+        Some(path) => format!("file:{}", path),
+        None => name.to_owned(),
+    }
+}
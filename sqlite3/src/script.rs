@@ -0,0 +1,42 @@
+//! SQL script tokenizer, finds the statement boundaries that `sqlite3_prepare_v2` doesn't report
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag, take_until},
+    character::complete::multispace1,
+    combinator::{opt, recognize, rest},
+    multi::many0,
+    sequence::{pair, tuple},
+    IResult,
+};
+
+/// `-- comment` up to the end of the line
+fn line_comment(input: &str) -> IResult<&str, &str> {
+    recognize(pair(tag("--"), opt(is_not("\n"))))(input)
+}
+
+/// `/* comment */`, an unterminated comment runs to the end of input
+fn block_comment(input: &str) -> IResult<&str, &str> {
+    alt((
+        recognize(tuple((tag("/*"), take_until("*/"), tag("*/")))),
+        recognize(pair(tag("/*"), rest)),
+    ))(input)
+}
+
+/// Skip the whitespace, comments and empty statements at the start of `sql`
+pub fn skip_blank(sql: &str) -> &str {
+    let mut blank = many0(alt((multispace1, line_comment, block_comment, tag(";"))));
+
+    match blank(sql) {
+        Ok((sql, _)) => sql,
+        Err(_) => sql,
+    }
+}
+
+/// Line of the byte `offset` of `sql`, starting from 1
+pub fn line_of(sql: &str, offset: usize) -> usize {
+    sql.as_bytes()[..offset]
+        .iter()
+        .filter(|c| **c == b'\n')
+        .count()
+        + 1
+}
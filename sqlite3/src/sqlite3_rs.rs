@@ -1,17 +1,27 @@
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
 /// ! sqlite3 c api wrapper mod
 ///
 use std::{
+    collections::VecDeque,
     ffi::{c_void, CStr, CString},
-    os::raw::c_char,
+    os::raw::{c_char, c_int},
     ptr::null_mut,
     slice::from_raw_parts,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
 };
 
-use super::error;
+use super::error::{self, Sqlite3Error};
+use super::options::{sqlite_uri, SqliteOptions};
+use super::script;
+use super::types;
 
 use sqlite3_sys::*;
 
-use anyhow::Result;
+use rdbc::Result;
 
 use rdbc::driver;
 
@@ -19,7 +29,18 @@ pub fn colunm_decltype(
     stmt: *mut sqlite3_stmt,
     i: i32,
 ) -> (driver::ColumnType, String, Option<u64>) {
-    let decltype = unsafe { CStr::from_ptr(sqlite3_column_decltype(stmt, i)) }.to_string_lossy();
+    let decltype = unsafe { sqlite3_column_decltype(stmt, i) };
+
+    // expression or subquery columns have no declared type
+    if decltype.is_null() {
+        return (driver::ColumnType::String, String::new(), None);
+    }
+
+    let decltype = unsafe { CStr::from_ptr(decltype) }.to_string_lossy();
+
+    if let Some(column_type) = types::decltype_column_type(&decltype) {
+        return (column_type, decltype.to_string(), None);
+    }
 
     match decltype.as_ref() {
         "INT" | "INTEGER" | "TINYINT" | "SMALLINT" | "MEDIUMINT" | "BIGINT"
@@ -42,11 +63,117 @@ pub fn colunm_decltype(
     }
 }
 
+/// Returns the storage class of column `i` of the current row
+pub fn column_storage_class(stmt: *mut sqlite3_stmt, i: i32) -> driver::ColumnType {
+    storage_class(unsafe { sqlite3_column_type(stmt, i) })
+}
+
+fn storage_class(sqlite_type: c_int) -> driver::ColumnType {
+    match sqlite_type {
+        SQLITE_INTEGER => driver::ColumnType::I64,
+        SQLITE_FLOAT => driver::ColumnType::F64,
+        SQLITE_BLOB => driver::ColumnType::Bytes,
+        _ => driver::ColumnType::String,
+    }
+}
+
+/// Column value of the current row, read from the statement or from a [`BufferedRow`]
+enum Column {
+    Stmt(*mut sqlite3_stmt, c_int),
+    Value(*mut sqlite3_value),
+}
+
+impl Column {
+    unsafe fn sqlite_type(&self) -> c_int {
+        match *self {
+            Column::Stmt(stmt, i) => sqlite3_column_type(stmt, i),
+            Column::Value(value) => sqlite3_value_type(value),
+        }
+    }
+
+    unsafe fn int64(&self) -> i64 {
+        match *self {
+            Column::Stmt(stmt, i) => sqlite3_column_int64(stmt, i),
+            Column::Value(value) => sqlite3_value_int64(value),
+        }
+    }
+
+    unsafe fn double(&self) -> f64 {
+        match *self {
+            Column::Stmt(stmt, i) => sqlite3_column_double(stmt, i),
+            Column::Value(value) => sqlite3_value_double(value),
+        }
+    }
+
+    unsafe fn text(&self) -> String {
+        let data = match *self {
+            Column::Stmt(stmt, i) => sqlite3_column_text(stmt, i),
+            Column::Value(value) => sqlite3_value_text(value),
+        };
+
+        CStr::from_ptr(data as *const c_char)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    unsafe fn blob(&self) -> Vec<u8> {
+        let (len, data) = match *self {
+            Column::Stmt(stmt, i) => (sqlite3_column_bytes(stmt, i), sqlite3_column_blob(stmt, i)),
+            Column::Value(value) => (sqlite3_value_bytes(value), sqlite3_value_blob(value)),
+        };
+
+        from_raw_parts(data as *const u8, len as usize).to_owned()
+    }
+}
+
+/// Row copied out of its statement by `sqlite3_value_dup`
+struct BufferedRow(Vec<*mut sqlite3_value>);
+
+// the values are private copies, not bound to the connection or statement
+unsafe impl Send for BufferedRow {}
+
+impl BufferedRow {
+    /// Copy the current row of `stmt`
+    fn copy(stmt: *mut sqlite3_stmt) -> Result<Self> {
+        let count = unsafe { sqlite3_column_count(stmt) };
+
+        let mut row = BufferedRow(Vec::with_capacity(count as usize));
+
+        for i in 0..count {
+            let value = unsafe { sqlite3_value_dup(sqlite3_column_value(stmt, i)) };
+
+            if value.is_null() {
+                return Err(error::native_error(
+                    SQLITE_NOMEM,
+                    "copy row value".to_owned(),
+                ));
+            }
+
+            row.0.push(value);
+        }
+
+        Ok(row)
+    }
+}
+
+impl Drop for BufferedRow {
+    fn drop(&mut self) {
+        for value in &self.0 {
+            unsafe { sqlite3_value_free(*value) };
+        }
+    }
+}
+
+/// Rows fetched to completion by [`Statement::execute_returning`]
+struct BufferedRows {
+    rows: VecDeque<BufferedRow>,
+    current: Option<BufferedRow>,
+}
+
 pub fn stmt_sql(stmt: *mut sqlite3_stmt) -> String {
     unsafe {
         CStr::from_ptr(sqlite3_expanded_sql(stmt))
             .to_string_lossy()
-            .to_owned()
             .to_string()
     }
 }
@@ -55,21 +182,297 @@ pub fn stmt_original_sql(stmt: *mut sqlite3_stmt) -> String {
     unsafe {
         CStr::from_ptr(sqlite3_sql(stmt))
             .to_string_lossy()
-            .to_owned()
             .to_string()
     }
 }
 
+/// Per-connection pragmas restored by [`Connection::reset`]
+const SESSION_PRAGMAS: &[&str] = &[
+    "foreign_keys",
+    "recursive_triggers",
+    "reverse_unordered_selects",
+    "query_only",
+    "ignore_check_constraints",
+    "automatic_index",
+    "cache_size",
+    "synchronous",
+    "temp_store",
+    "busy_timeout",
+];
+
+/// Owned sqlite3 connection handle, closed when the last [`Connection`],
+/// [`Transaction`], [`Statement`] or [`Rows`] using it is dropped.
+struct DbHandle {
+    ptr: *mut sqlite3,
+    /// Deadline of the running step, checked by [`progress_handler`]
+    deadline: Mutex<Option<Instant>>,
+    /// Set when [`progress_handler`] interrupts a step past its deadline
+    timed_out: AtomicBool,
+    /// Set by [`authorizer`] while preparing a statement that changes the session state
+    session_statement: AtomicBool,
+    /// Set once such a statement runs, cleared by [`Connection::reset`]
+    session_changed: AtomicBool,
+    /// Set by [`authorizer`] while preparing a statement that changes the schema
+    schema_statement: AtomicBool,
+    /// Set once such a statement runs, cleared by [`Connection::begin`]
+    schema_changed: AtomicBool,
+}
+
+impl DbHandle {
+    /// Clears the flags [`authorizer`] sets while preparing a statement
+    fn start_prepare(&self) {
+        self.session_statement.store(false, Ordering::SeqCst);
+        self.schema_statement.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Number of virtual machine instructions between [`progress_handler`] calls
+const PROGRESS_INSTRUCTIONS: c_int = 1000;
+
+/// Interrupts the running step once its deadline has passed, `ctx` is the [`DbHandle`]
+extern "C" fn progress_handler(ctx: *mut c_void) -> c_int {
+    let handle = unsafe { &*(ctx as *const DbHandle) };
+
+    let deadline = match handle.deadline.lock() {
+        Ok(deadline) => *deadline,
+        Err(_) => None,
+    };
+
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => {
+            handle.timed_out.store(true, Ordering::SeqCst);
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Flags the statements changing the session state restored by [`Connection::reset`]:
+/// [`SESSION_PRAGMAS`] assignments, `ATTACH` and temp tables/views, and the statements
+/// creating, altering or dropping a schema object. `ctx` is the [`DbHandle`]
+extern "C" fn authorizer(
+    ctx: *mut c_void,
+    action: c_int,
+    arg1: *const c_char,
+    arg2: *const c_char,
+    _database: *const c_char,
+    _trigger: *const c_char,
+) -> c_int {
+    let handle = unsafe { &*(ctx as *const DbHandle) };
+
+    let changes_session = match action {
+        SQLITE_ATTACH | SQLITE_CREATE_TEMP_TABLE | SQLITE_CREATE_TEMP_VIEW => true,
+        // reading a pragma has no argument
+        SQLITE_PRAGMA if !arg1.is_null() && !arg2.is_null() => {
+            let name = unsafe { CStr::from_ptr(arg1) }.to_string_lossy();
+
+            SESSION_PRAGMAS
+                .iter()
+                .any(|pragma| name.eq_ignore_ascii_case(pragma))
+        }
+        _ => false,
+    };
+
+    if changes_session {
+        handle.session_statement.store(true, Ordering::SeqCst);
+    }
+
+    let changes_schema = matches!(
+        action,
+        SQLITE_CREATE_INDEX..=SQLITE_CREATE_VIEW
+            | SQLITE_DROP_INDEX..=SQLITE_DROP_VIEW
+            | SQLITE_ALTER_TABLE
+            | SQLITE_CREATE_VTABLE
+            | SQLITE_DROP_VTABLE
+    );
+
+    if changes_schema {
+        handle.schema_statement.store(true, Ordering::SeqCst);
+    }
+
+    SQLITE_OK
+}
+
+// SAFETY: [`Connection::open_with`] always opens connections in serialized mode
+// (`SQLITE_OPEN_FULLMUTEX`, whatever the options), so sqlite3 serializes the calls
+// made on the same connection from different threads.
+unsafe impl Send for DbHandle {}
+unsafe impl Sync for DbHandle {}
+
+impl Drop for DbHandle {
+    fn drop(&mut self) {
+        log::debug!("drop db {:?}", self.ptr);
+
+        // all statements are finalized, they hold a reference to this handle
+        let r = unsafe { sqlite3_close(self.ptr) };
+
+        // debug output
+        if r != SQLITE_OK {
+            log::error!("close sqlite3 conn failed: code({})", r);
+        }
+    }
+}
+
+/// Owned prepared statement handle, finalized when the last [`Statement`]
+/// or [`Rows`] using it is dropped.
+struct StmtHandle {
+    ptr: *mut sqlite3_stmt,
+    /// The connection must outlive its statements
+    db: Arc<DbHandle>,
+    /// Running the statement changes the session state, see [`authorizer`]
+    changes_session: bool,
+    /// Running the statement changes the schema, see [`authorizer`]
+    changes_schema: bool,
+    /// Shared with the [`InterruptHandle`]s of the statement
+    step_state: Mutex<StepState>,
+}
+
+/// Whether the statement is in `sqlite3_step`, see [`InterruptHandle::interrupt`]
+#[derive(Default)]
+struct StepState {
+    running: bool,
+    /// Set by [`InterruptHandle::interrupt`] during the running step
+    interrupted: bool,
+}
+
+// SAFETY: `sqlite3_stmt` calls lock the mutex of their connection, which exists
+// because the connection is opened in serialized mode, see `DbHandle`. So a
+// [`Statement`] and its [`Rows`], which share the handle, may be stepped from
+// different threads. The async driver goes further and only uses them on the worker
+// thread owning their connection. `sqlite3_finalize` runs once, on the last drop.
+unsafe impl Send for StmtHandle {}
+unsafe impl Sync for StmtHandle {}
+
+impl StmtHandle {
+    /// Take ownership of `ptr`, just prepared on `db`
+    fn new(ptr: *mut sqlite3_stmt, db: Arc<DbHandle>) -> Self {
+        let changes_session = db.session_statement.swap(false, Ordering::SeqCst);
+
+        let changes_schema = db.schema_statement.swap(false, Ordering::SeqCst);
+
+        Self {
+            ptr,
+            db,
+            changes_session,
+            changes_schema,
+            step_state: Default::default(),
+        }
+    }
+
+    /// `sqlite3_step` interrupted after `timeout`, returns `SQLITE_ROW` or `SQLITE_DONE`
+    fn step(&self, timeout: Option<Duration>) -> Result<c_int> {
+        if self.changes_session {
+            self.db.session_changed.store(true, Ordering::SeqCst);
+        }
+
+        if self.changes_schema {
+            self.db.schema_changed.store(true, Ordering::SeqCst);
+        }
+
+        *self.db.deadline.lock().unwrap() = timeout.map(|timeout| Instant::now() + timeout);
+
+        self.db.timed_out.store(false, Ordering::SeqCst);
+
+        *self.step_state.lock().unwrap() = StepState {
+            running: true,
+            interrupted: false,
+        };
+
+        let rc = unsafe { sqlite3_step(self.ptr) };
+
+        let interrupted = {
+            let mut state = self.step_state.lock().unwrap();
+
+            state.running = false;
+
+            state.interrupted
+        };
+
+        *self.db.deadline.lock().unwrap() = None;
+
+        match rc {
+            // interrupted after its last instruction, sqlite3 keeps interrupting
+            // the connection until this statement is reset
+            SQLITE_ROW if interrupted => {
+                unsafe { sqlite3_reset(self.ptr) };
+
+                Err(error::native_error(
+                    SQLITE_INTERRUPT,
+                    "interrupted".to_owned(),
+                ))
+            }
+            SQLITE_ROW | SQLITE_DONE => Ok(rc),
+            SQLITE_INTERRUPT if self.db.timed_out.load(Ordering::SeqCst) => Err(rdbc::Error::from(
+                Sqlite3Error::Timeout(timeout.unwrap_or_default()),
+            )),
+            _ => Err(error::db_native_error(self.db.ptr, rc)),
+        }
+    }
+}
+
+impl Drop for StmtHandle {
+    fn drop(&mut self) {
+        unsafe { sqlite3_finalize(self.ptr) };
+    }
+}
+
+/// Interrupts the step running on a statement from any thread, via `sqlite3_interrupt`
+#[derive(Clone)]
+pub struct InterruptHandle {
+    /// Doesn't keep the statement alive, the handle is a no-op once it is dropped
+    stmt: Weak<StmtHandle>,
+}
+
+impl InterruptHandle {
+    /// Interrupt the statement if it is in `sqlite3_step`, otherwise do nothing.
+    ///
+    /// `sqlite3_interrupt` applies to the whole connection, so another statement
+    /// of the connection whose rows are being read may fail too.
+    pub fn interrupt(&self) {
+        if let Some(stmt) = self.stmt.upgrade() {
+            // the lock keeps the step from finishing, the interrupt can't hit the next one
+            let mut state = stmt.step_state.lock().unwrap();
+
+            if state.running {
+                state.interrupted = true;
+
+                unsafe { sqlite3_interrupt(stmt.db.ptr) };
+            }
+        }
+    }
+}
+
+impl driver::CancelHandle for InterruptHandle {
+    fn cancel(&self) -> driver::Cancel {
+        let (fut, waker) = driver::Cancel::new();
+
+        self.interrupt();
+
+        waker.lock().unwrap().ready(Ok(()));
+
+        fut
+    }
+}
+
 /// sqlite connection object
 pub struct Connection {
-    db: *mut sqlite3,
+    handle: Arc<DbHandle>,
     pub id: String,
+    /// [`SESSION_PRAGMAS`] values right after the connection was opened
+    pragmas: Vec<i64>,
 }
 
-unsafe impl Send for Connection {}
-
 impl Connection {
+    fn db(&self) -> *mut sqlite3 {
+        self.handle.ptr
+    }
+
     pub fn open(name: &str) -> Result<Self> {
+        Self::open_with(name, &SqliteOptions::default())
+    }
+
+    /// Open `name`, a file name, `file:` uri or `sqlite3://` url, see [`SqliteOptions`]
+    pub fn open_with(name: &str, options: &SqliteOptions) -> Result<Self> {
         unsafe {
             assert!(
                 sqlite3_threadsafe() != 0,
@@ -79,15 +482,27 @@ impl Connection {
 
         let mut db = std::ptr::null_mut();
 
-        let flags =
-            SQLITE_OPEN_URI | SQLITE_OPEN_CREATE | SQLITE_OPEN_READWRITE | SQLITE_OPEN_NOMUTEX;
+        // the `Send`/`Sync` impls of the handles rely on serialized mode
+        let flags = (options.flags() & !SQLITE_OPEN_NOMUTEX) | SQLITE_OPEN_FULLMUTEX;
 
-        log::debug!("open sqlite3 database: {} {:X}", name, flags);
+        let uri = sqlite_uri(name);
 
-        let c_name = CString::new(name)?;
+        log::debug!("open sqlite3 database: {} {:X}", uri, flags);
+
+        let c_name = CString::new(uri).map_err(Sqlite3Error::from)?;
+
+        let c_vfs = match &options.vfs {
+            Some(vfs) => Some(CString::new(vfs.as_str()).map_err(Sqlite3Error::from)?),
+            None => None,
+        };
 
         unsafe {
-            let r = sqlite3_open_v2(c_name.as_ptr(), &mut db, flags, std::ptr::null());
+            let r = sqlite3_open_v2(
+                c_name.as_ptr(),
+                &mut db,
+                flags,
+                c_vfs.as_ref().map_or(std::ptr::null(), |vfs| vfs.as_ptr()),
+            );
 
             if r != SQLITE_OK {
                 let e = if db.is_null() {
@@ -105,22 +520,251 @@ impl Connection {
                     e
                 };
 
-                return Err(e);
+                Err(e)
             } else {
-                return Ok(Self {
+                // report e.g. SQLITE_CONSTRAINT_UNIQUE instead of SQLITE_CONSTRAINT
+                sqlite3_extended_result_codes(db, 1);
+
+                let handle = Arc::new(DbHandle {
+                    ptr: db,
+                    deadline: Mutex::new(None),
+                    timed_out: AtomicBool::new(false),
+                    session_statement: AtomicBool::new(false),
+                    session_changed: AtomicBool::new(false),
+                    schema_statement: AtomicBool::new(false),
+                    schema_changed: AtomicBool::new(false),
+                });
+
+                // the handle address is stable, it is closed before being freed
+                sqlite3_progress_handler(
                     db,
+                    PROGRESS_INSTRUCTIONS,
+                    Some(progress_handler),
+                    Arc::as_ptr(&handle) as *mut c_void,
+                );
+
+                sqlite3_set_authorizer(db, Some(authorizer), Arc::as_ptr(&handle) as *mut c_void);
+
+                let mut conn = Self {
+                    handle,
                     id: format!("{:?}", db),
-                });
+                    pragmas: vec![],
+                };
+
+                conn.pragmas = conn.session_pragmas()?;
+
+                Ok(conn)
             }
         }
     }
 
-    pub fn begin(&mut self) -> Result<Transaction> {
+    /// Restore the connection session state before it is reused: rollback the open
+    /// transaction, restore the [`SESSION_PRAGMAS`], detach the attached databases
+    /// and drop temp tables/views.
+    ///
+    /// The session state is only inspected if a statement changing it has run
+    /// since the last reset, see [`authorizer`].
+    ///
+    /// Returns `true` if anything had to be cleaned up.
+    pub fn reset(&mut self) -> Result<bool> {
+        let mut dirty = false;
+
+        if unsafe { sqlite3_get_autocommit(self.db()) } == 0 {
+            log::debug!("rollback open transaction of connection {}", self.id);
+
+            self.exec("ROLLBACK")?;
+
+            dirty = true;
+        }
+
+        if !self.handle.session_changed.load(Ordering::SeqCst) {
+            return Ok(dirty);
+        }
+
+        let pragmas = self.session_pragmas()?;
+
+        for ((name, value), initial) in SESSION_PRAGMAS
+            .iter()
+            .zip(pragmas)
+            .zip(self.pragmas.clone())
+        {
+            if value != initial {
+                self.exec(&format!("PRAGMA {} = {}", name, initial))?;
+
+                dirty = true;
+            }
+        }
+
+        for name in self.attached_databases()? {
+            self.exec(&format!(
+                "DETACH DATABASE \"{}\"",
+                name.replace('"', "\"\"")
+            ))?;
+
+            dirty = true;
+        }
+
+        for (object_type, name) in self.temp_objects()? {
+            self.exec(&format!(
+                "DROP {} temp.\"{}\"",
+                object_type,
+                name.replace('"', "\"\"")
+            ))?;
+
+            dirty = true;
+        }
+
+        // the statements above changed the session state too
+        self.handle.session_changed.store(false, Ordering::SeqCst);
+
+        Ok(dirty)
+    }
+
+    /// Current values of [`SESSION_PRAGMAS`]
+    fn session_pragmas(&mut self) -> Result<Vec<i64>> {
+        let query = format!(
+            "SELECT * FROM {}",
+            SESSION_PRAGMAS
+                .iter()
+                .map(|name| format!("pragma_{}()", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let mut stmt = self.prepare(&query)?;
+
+        let mut rows = stmt.query(vec![])?;
+
+        let mut values = vec![];
+
+        if rows.next()? {
+            for i in 0..SESSION_PRAGMAS.len() {
+                if let driver::Value::I64(value) = rows.get(
+                    driver::Placeholder::Index(i as u64),
+                    driver::ColumnType::I64,
+                )? {
+                    values.push(value);
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Returns the names of the databases attached to this connection
+    fn attached_databases(&mut self) -> Result<Vec<String>> {
+        let mut stmt = self.prepare("SELECT name FROM pragma_database_list() WHERE seq > 1")?;
+
+        let mut rows = stmt.query(vec![])?;
+
+        let mut names = vec![];
+
+        while rows.next()? {
+            if let driver::Value::String(name) =
+                rows.get(driver::Placeholder::Index(0), driver::ColumnType::String)?
+            {
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Returns (type, name) of the temp tables and views created on this connection
+    fn temp_objects(&mut self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.prepare(
+            "SELECT type, name FROM temp.sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%'",
+        )?;
+
+        let mut rows = stmt.query(vec![])?;
+
+        let mut objects = vec![];
+
+        while rows.next()? {
+            let object_type =
+                rows.get(driver::Placeholder::Index(0), driver::ColumnType::String)?;
+            let name = rows.get(driver::Placeholder::Index(1), driver::ColumnType::String)?;
+
+            if let (driver::Value::String(object_type), driver::Value::String(name)) =
+                (object_type, name)
+            {
+                objects.push((object_type.to_uppercase(), name));
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// Begin a transaction, sqlite3 transactions are always serializable so any
+    /// [`driver::IsolationLevel`] is accepted.
+    pub fn begin(&mut self, options: driver::TransactionOptions) -> Result<Transaction> {
+        let sql = match options.locking_mode {
+            driver::LockingMode::Immediate => "BEGIN IMMEDIATE",
+            driver::LockingMode::Exclusive => "BEGIN EXCLUSIVE",
+            _ => "BEGIN DEFERRED",
+        };
+
+        if options.read_only {
+            self.exec("PRAGMA query_only = 1")?;
+        }
+
+        self.handle.schema_changed.store(false, Ordering::SeqCst);
+
+        if let Err(err) = self.exec(sql) {
+            if options.read_only {
+                _ = self.exec("PRAGMA query_only = 0");
+            }
+
+            return Err(err);
+        }
+
+        Ok(Transaction {
+            conn: Connection {
+                handle: self.handle.clone(),
+                id: self.id.clone(),
+                pragmas: vec![],
+            },
+            finished: false,
+            read_only: options.read_only,
+            id: uuid::Uuid::new_v4().to_string(), // Use the randomly generated uuid as tx id
+        })
+    }
+
+    /// Check the connection is still usable.
+    ///
+    /// Fails if the database file has been deleted, renamed or replaced since
+    /// the connection was opened.
+    pub fn ping(&mut self) -> Result<()> {
+        let mut moved: c_int = 0;
+
+        let schema = CString::new("main").unwrap();
+
         let rc = unsafe {
-            let c_str = CString::new("BEGIN").unwrap();
+            sqlite3_file_control(
+                self.db(),
+                schema.as_ptr(),
+                SQLITE_FCNTL_HAS_MOVED,
+                &mut moved as *mut c_int as *mut c_void,
+            )
+        };
 
+        // in-memory databases have no file and return SQLITE_NOTFOUND
+        if rc == SQLITE_OK && moved != 0 {
+            return Err(rdbc::Error::from(Sqlite3Error::DatabaseMoved));
+        }
+
+        self.exec("SELECT 1")
+    }
+
+    /// Run `sql` without returning rows, via `sqlite3_exec`
+    pub fn exec(&mut self, sql: &str) -> Result<()> {
+        let c_str = CString::new(sql).map_err(Sqlite3Error::from)?;
+
+        self.handle.start_prepare();
+
+        let rc = unsafe {
             sqlite3_exec(
-                self.db,
+                self.db(),
                 c_str.as_ptr(),
                 None,
                 null_mut::<c_void>(),
@@ -128,137 +772,219 @@ impl Connection {
             )
         };
 
+        if self.handle.session_statement.swap(false, Ordering::SeqCst) {
+            self.handle.session_changed.store(true, Ordering::SeqCst);
+        }
+
+        if self.handle.schema_statement.swap(false, Ordering::SeqCst) {
+            self.handle.schema_changed.store(true, Ordering::SeqCst);
+        }
+
         if rc != SQLITE_OK {
-            return Err(error::db_native_error(self.db, rc));
+            return Err(error::error_with_sql(self.db(), rc, sql));
         }
 
-        Ok(Transaction {
-            conn: Connection {
-                db: self.db,
-                id: self.id.clone(),
-            },
-            finished: false,
-            id: uuid::Uuid::new_v4().to_string(), // Use the randomly generated uuid as tx id
-        })
+        Ok(())
     }
 
     pub fn prepare(&mut self, query: &str) -> Result<Statement> {
-        let sqlite3_query = CString::new(query)?;
+        let sqlite3_query = CString::new(query).map_err(Sqlite3Error::from)?;
 
         let mut stmt = null_mut();
 
+        let mut tail = std::ptr::null::<c_char>();
+
+        self.handle.start_prepare();
+
         let rc = unsafe {
             sqlite3_prepare_v2(
-                self.db,
+                self.db(),
                 sqlite3_query.as_ptr(),
                 sqlite3_query.as_bytes().len() as i32,
                 &mut stmt,
-                null_mut::<*const c_char>(),
+                &mut tail,
             )
         };
 
         if rc != SQLITE_OK {
-            return Err(error::error_with_sql(self.db, rc, query));
+            return Err(error::error_with_sql(self.db(), rc, query));
         }
 
         // If the input text contains no SQL (if the input is an empty string or a comment) then *ppStmt is set to NULL.
         if stmt.is_null() {
-            return Err(anyhow::anyhow!("invalid input sql {}", query));
+            return Err(Sqlite3Error::InvalidSql(query.to_owned()).into());
+        }
+
+        let handle = Arc::new(StmtHandle::new(stmt, self.handle.clone()));
+
+        // only the first statement is prepared, don't silently drop the others
+        let tail = unsafe { tail.offset_from(sqlite3_query.as_ptr()) } as usize;
+
+        if !script::skip_blank(&query[tail..]).is_empty() {
+            return Err(Sqlite3Error::MultipleStatements(query.to_owned()).into());
         }
 
         Ok(Statement {
-            db: self.db,
-            stmt,
+            handle,
+            timeout: None,
             id: format!("{:?}", stmt),
         })
     }
-}
 
-impl Drop for Connection {
-    fn drop(&mut self) {
-        if !self.db.is_null() {
-            log::debug!("drop db {:?}", self.db);
+    /// Run every statement of `script` in order, ignoring the rows they return,
+    /// see [`driver::Connection::execute_script`]
+    pub fn execute_script(&mut self, script: &str) -> Result<driver::ExecuteResult> {
+        let c_script = CString::new(script).map_err(Sqlite3Error::from)?;
 
-            let r = unsafe { sqlite3_close(self.db) }; // ignore result .
+        let total_changes = unsafe { sqlite3_total_changes(self.db()) };
 
-            self.db = std::ptr::null_mut(); // set db ptr to null to preventing twice drop
+        let mut offset = 0;
 
-            // debug output
-            if r != SQLITE_OK {
-                log::error!("close sqlite3 conn failed: code({})", r);
+        for index in 0.. {
+            offset = script.len() - script::skip_blank(&script[offset..]).len();
+
+            if offset == script.len() {
+                break;
+            }
+
+            let position = driver::ScriptPosition {
+                index,
+                offset,
+                line: script::line_of(script, offset),
+            };
+
+            let mut stmt = null_mut();
+
+            let mut tail = std::ptr::null::<c_char>();
+
+            self.handle.start_prepare();
+
+            let rc = unsafe {
+                sqlite3_prepare_v2(
+                    self.db(),
+                    c_script.as_ptr().add(offset),
+                    (script.len() - offset) as c_int,
+                    &mut stmt,
+                    &mut tail,
+                )
+            };
+
+            if rc != SQLITE_OK {
+                return Err(error::db_native_error(self.db(), rc).context(position));
             }
+
+            // the rest is blank for sqlite3 but not for the tokenizer
+            if stmt.is_null() {
+                break;
+            }
+
+            let handle = StmtHandle::new(stmt, self.handle.clone());
+
+            log::trace!("execute script sql {}", stmt_original_sql(stmt));
+
+            while handle
+                .step(None)
+                .map_err(|err| err.context(position.clone()))?
+                == SQLITE_ROW
+            {}
+
+            offset = unsafe { tail.offset_from(c_script.as_ptr()) } as usize;
         }
+
+        Ok(driver::ExecuteResult {
+            last_insert_id: unsafe { sqlite3_last_insert_rowid(self.db()) } as u64,
+            raws_affected: unsafe { sqlite3_total_changes(self.db()) - total_changes } as u64,
+        })
     }
 }
 
 pub struct Statement {
-    db: *mut sqlite3,
-    stmt: *mut sqlite3_stmt,
+    handle: Arc<StmtHandle>,
+    /// Run time limit of each step
+    timeout: Option<Duration>,
     pub id: String,
 }
 
-fn get_bind_index(stmt: *mut sqlite3_stmt, pos: driver::Placeholder) -> anyhow::Result<i32> {
+fn get_bind_index(stmt: *mut sqlite3_stmt, pos: driver::Placeholder) -> Result<i32> {
     let index = match &pos {
-        driver::Placeholder::Index(index) => *index as i32,
+        driver::Placeholder::Index(index) => {
+            i32::try_from(*index).map_err(|_| Sqlite3Error::OutOfRange(*index))?
+        }
         driver::Placeholder::Name(name) => {
-            let c_named = CString::new(name.as_str())?;
+            let c_named = CString::new(name.as_str()).map_err(Sqlite3Error::from)?;
             unsafe { sqlite3_bind_parameter_index(stmt, c_named.as_ptr()) }
         }
     };
 
     if index == 0 {
-        return Err(anyhow::Error::new(error::Sqlite3Error::BindArgError(
+        return Err(rdbc::Error::from(Sqlite3Error::BindArgError(
             stmt_original_sql(stmt),
             format!("{:?}", pos),
         )));
     }
 
-    return Ok(index);
+    Ok(index)
 }
 
 impl Statement {
-    unsafe fn bind_args(&mut self, args: Vec<rdbc::Arg>) -> anyhow::Result<()> {
-        sqlite3_clear_bindings(self.stmt);
+    fn db(&self) -> *mut sqlite3 {
+        self.handle.db.ptr
+    }
+
+    fn stmt(&self) -> *mut sqlite3_stmt {
+        self.handle.ptr
+    }
+
+    unsafe fn bind_args(&mut self, args: Vec<rdbc::Arg>) -> Result<()> {
+        // a stepped statement must be reset before it can be bound again
+        sqlite3_reset(self.stmt());
+        sqlite3_clear_bindings(self.stmt());
 
         for arg in args {
-            let index = get_bind_index(self.stmt, arg.pos)?;
+            let index = get_bind_index(self.stmt(), arg.pos)?;
 
-            let rc = match arg.value {
+            let rc = match types::encode(arg.value)? {
                 driver::Value::Bytes(bytes) => {
                     let ptr = bytes.as_ptr();
                     let len = bytes.len();
                     sqlite3_bind_blob(
-                        self.stmt,
+                        self.stmt(),
                         index,
                         ptr as *const c_void,
                         len as i32,
-                        Some(std::mem::transmute(SQLITE_TRANSIENT as usize)),
+                        Some(std::mem::transmute::<isize, sqlite3_callback>(
+                            SQLITE_TRANSIENT as isize,
+                        )),
                     )
                 }
-                driver::Value::F64(f64) => sqlite3_bind_double(self.stmt, index, f64),
+                driver::Value::F64(f64) => sqlite3_bind_double(self.stmt(), index, f64),
 
-                driver::Value::I64(i64) => sqlite3_bind_int64(self.stmt, index, i64),
+                driver::Value::I64(i64) => sqlite3_bind_int64(self.stmt(), index, i64),
 
                 driver::Value::String(str) => {
-                    let str = CString::new(str)?;
+                    let str = CString::new(str).map_err(Sqlite3Error::from)?;
 
                     let ptr = str.as_ptr();
                     let len = str.as_bytes().len() as i32;
 
                     sqlite3_bind_text(
-                        self.stmt,
+                        self.stmt(),
                         index,
                         ptr,
                         len,
-                        Some(std::mem::transmute(SQLITE_TRANSIENT as usize)),
+                        Some(std::mem::transmute::<isize, sqlite3_callback>(
+                            SQLITE_TRANSIENT as isize,
+                        )),
                     )
                 }
 
                 driver::Value::Null => SQLITE_OK,
+
+                value => unreachable!("unencoded value {:?}", value),
             };
 
             if rc != SQLITE_OK {
-                return Err(error::db_native_error(self.db, rc));
+                return Err(error::db_native_error(self.db(), rc));
             }
         }
 
@@ -268,70 +994,144 @@ impl Statement {
     pub fn execute(&mut self, args: Vec<rdbc::Arg>) -> Result<driver::ExecuteResult> {
         unsafe { self.bind_args(args) }?;
 
-        log::trace!("execute sql {}", stmt_sql(self.stmt));
+        log::trace!("execute sql {}", stmt_sql(self.stmt()));
 
-        let rc = unsafe { sqlite3_step(self.stmt) };
+        let step = self.handle.step(self.timeout);
 
-        // unsafe { sqlite3_reset(self.stmt) };
+        // don't keep the statement active while it sits in the rdbc statement cache
+        unsafe { sqlite3_reset(self.stmt()) };
 
-        match rc {
+        match step? {
             SQLITE_DONE => {
-                let last_insert_id = unsafe { sqlite3_last_insert_rowid(self.db) } as u64;
-                let raws_affected = unsafe { sqlite3_changes(self.db) } as u64;
+                let last_insert_id = unsafe { sqlite3_last_insert_rowid(self.db()) } as u64;
+                let raws_affected = unsafe { sqlite3_changes(self.db()) } as u64;
 
-                return Ok(driver::ExecuteResult {
+                Ok(driver::ExecuteResult {
                     last_insert_id,
                     raws_affected,
-                });
-            }
-            SQLITE_ROW => {
-                return Err(anyhow::Error::new(error::Sqlite3Error::UnexpectRows));
+                })
             }
-            _ => {
-                return Err(error::db_native_error(self.db, rc));
-            }
-        };
+            _ => Err(rdbc::Error::from(Sqlite3Error::UnexpectRows)),
+        }
+    }
+
+    /// Execute once for each args of `batch`, see [`driver::Statement::execute_batch`]
+    pub fn execute_batch(
+        &mut self,
+        batch: Vec<Vec<rdbc::Arg>>,
+    ) -> Result<Vec<driver::ExecuteResult>> {
+        batch
+            .into_iter()
+            .enumerate()
+            .map(|(index, args)| {
+                self.execute(args)
+                    .map_err(|err| err.context(format!("execute batch index {} failed", index)))
+            })
+            .collect()
     }
 
     pub fn num_input(&self) -> Option<u32> {
-        Some(unsafe { sqlite3_bind_parameter_count(self.stmt) } as u32)
+        Some(unsafe { sqlite3_bind_parameter_count(self.stmt()) } as u32)
+    }
+
+    /// Limit the run time of each step, the step fails with [`Sqlite3Error::Timeout`]
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            stmt: Arc::downgrade(&self.handle),
+        }
     }
 
     pub fn query(&mut self, args: Vec<rdbc::Arg>) -> Result<Rows> {
         unsafe { self.bind_args(args) }?;
 
-        return Ok(Rows {
-            db: self.db,
-            stmt: self.stmt,
+        Ok(Rows {
+            handle: self.handle.clone(),
+            timeout: self.timeout,
+            columns: None,
+            has_next: false,
+            buffered: None,
+            id: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Execute a DML statement with a RETURNING clause.
+    ///
+    /// sqlite3 updates the change counters when the statement completes, so
+    /// the returned rows are fetched and buffered before the result is known.
+    pub fn execute_returning(
+        &mut self,
+        args: Vec<rdbc::Arg>,
+    ) -> Result<(driver::ExecuteResult, Rows)> {
+        unsafe { self.bind_args(args) }?;
+
+        log::trace!("execute returning sql {}", stmt_sql(self.stmt()));
+
+        let mut rows = Rows {
+            handle: self.handle.clone(),
+            timeout: self.timeout,
             columns: None,
             has_next: false,
+            buffered: None,
             id: uuid::Uuid::new_v4().to_string(),
+        };
+
+        let buffered = self.buffer_rows(&mut rows);
+
+        let result = driver::ExecuteResult {
+            last_insert_id: unsafe { sqlite3_last_insert_rowid(self.db()) } as u64,
+            raws_affected: unsafe { sqlite3_changes(self.db()) } as u64,
+        };
+
+        unsafe { sqlite3_reset(self.stmt()) };
+
+        rows.buffered = Some(BufferedRows {
+            rows: buffered?,
+            current: None,
         });
+
+        Ok((result, rows))
     }
-}
 
-impl Drop for Statement {
-    fn drop(&mut self) {
-        if !self.stmt.is_null() {
-            unsafe { sqlite3_finalize(self.stmt) };
-            self.stmt = null_mut();
+    /// Step to completion, copying the returned rows
+    fn buffer_rows(&mut self, rows: &mut Rows) -> Result<VecDeque<BufferedRow>> {
+        let mut buffered = VecDeque::new();
+
+        while self.handle.step(self.timeout)? == SQLITE_ROW {
+            // the column storage classes are known on the first row
+            if rows.columns.is_none() {
+                rows.has_next = true;
+                rows.colunms()?;
+                rows.has_next = false;
+            }
+
+            buffered.push_back(BufferedRow::copy(self.stmt())?);
         }
+
+        rows.colunms()?;
+
+        Ok(buffered)
     }
 }
 
 pub struct Transaction {
     conn: Connection,
     finished: bool,
+    /// `PRAGMA query_only` is turned off when the transaction finishes
+    read_only: bool,
     pub id: String,
 }
 
 impl Transaction {
-    fn _rollback(&self) -> anyhow::Result<()> {
+    fn _rollback(&mut self) -> Result<()> {
         let rc = unsafe {
             let c_str = CString::new("ROLLBACK").unwrap();
 
             sqlite3_exec(
-                self.conn.db,
+                self.conn.db(),
                 c_str.as_ptr(),
                 None,
                 null_mut::<c_void>(),
@@ -339,19 +1139,31 @@ impl Transaction {
             )
         };
 
+        self.restore_query_only();
+
         if rc != SQLITE_OK {
-            return Err(error::error_with_sql(self.conn.db, rc, "ROLLBACK"));
+            return Err(error::error_with_sql(self.conn.db(), rc, "ROLLBACK"));
         }
 
         Ok(())
     }
 
+    fn restore_query_only(&mut self) {
+        if self.read_only {
+            self.read_only = false;
+
+            if let Err(err) = self.conn.exec("PRAGMA query_only = 0") {
+                log::error!("restore query_only of connection {}: {}", self.conn.id, err);
+            }
+        }
+    }
+
     pub fn commit(&mut self) -> Result<()> {
         let rc = unsafe {
             let c_str = CString::new("COMMIT").unwrap();
 
             sqlite3_exec(
-                self.conn.db,
+                self.conn.db(),
                 c_str.as_ptr(),
                 None,
                 null_mut::<c_void>(),
@@ -359,24 +1171,80 @@ impl Transaction {
             )
         };
 
-        self.finished = true;
-
         if rc != SQLITE_OK {
-            Err(error::error_with_sql(self.conn.db, rc, "COMMIT"))
-        } else {
-            Ok(())
+            let err = error::error_with_sql(self.conn.db(), rc, "COMMIT");
+
+            // a failed COMMIT (e.g. SQLITE_BUSY) leaves the transaction open, which is
+            // rolled back on drop unless it is retried, some errors end it already
+            if unsafe { sqlite3_get_autocommit(self.conn.db()) } != 0 {
+                self.finished = true;
+
+                self.restore_query_only();
+            }
+
+            return Err(err);
         }
+
+        self.finished = true;
+
+        self.restore_query_only();
+
+        Ok(())
     }
 
     pub fn prepare(&mut self, query: &str) -> Result<Statement> {
         self.conn.prepare(query)
     }
 
+    pub fn execute_script(&mut self, script: &str) -> Result<driver::ExecuteResult> {
+        self.conn.execute_script(script)
+    }
+
     pub fn rollback(&mut self) -> Result<()> {
         self.finished = true;
 
         self._rollback()
     }
+
+    pub fn savepoint(&mut self, name: &str) -> Result<()> {
+        self.conn
+            .exec(&format!("SAVEPOINT {}", quote_identifier(name)))
+    }
+
+    pub fn release(&mut self, name: &str) -> Result<()> {
+        self.conn
+            .exec(&format!("RELEASE {}", quote_identifier(name)))
+    }
+
+    pub fn rollback_to(&mut self, name: &str) -> Result<()> {
+        self.conn
+            .exec(&format!("ROLLBACK TO {}", quote_identifier(name)))
+    }
+
+    /// Reports whether a statement changed the schema since the transaction began
+    pub fn schema_changes(&self) -> Arc<dyn driver::SchemaChanges> {
+        Arc::new(SchemaTracker {
+            db: Arc::downgrade(&self.conn.handle),
+        })
+    }
+}
+
+/// [`driver::SchemaChanges`] of a [`Transaction`], set by the statements flagged by [`authorizer`]
+struct SchemaTracker {
+    db: Weak<DbHandle>,
+}
+
+impl driver::SchemaChanges for SchemaTracker {
+    fn changed(&self) -> bool {
+        self.db
+            .upgrade()
+            .is_none_or(|db| db.schema_changed.load(Ordering::SeqCst))
+    }
+}
+
+/// Quote `name` as sql identifier
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
 }
 
 impl Drop for Transaction {
@@ -386,37 +1254,49 @@ impl Drop for Transaction {
             _ = self._rollback();
             self.finished = true;
         }
-
-        self.conn.db = null_mut();
     }
 }
 
+/// Result set of a [`Statement`], which stays alive until the rows are dropped.
 pub struct Rows {
-    db: *mut sqlite3,
-    stmt: *mut sqlite3_stmt,
+    handle: Arc<StmtHandle>,
+    /// Run time limit of each step, see [`Statement::set_timeout`]
+    timeout: Option<Duration>,
     columns: Option<Vec<driver::ColumnMetaData>>,
     has_next: bool,
+    /// Set by [`Statement::execute_returning`], the statement is already reset
+    buffered: Option<BufferedRows>,
     pub id: String,
 }
 
 impl Rows {
+    fn stmt(&self) -> *mut sqlite3_stmt {
+        self.handle.ptr
+    }
+
     pub fn colunms(&mut self) -> Result<&Vec<driver::ColumnMetaData>> {
         if self.columns.is_none() {
             let mut columns = vec![];
 
             unsafe {
-                let count = sqlite3_column_count(self.stmt);
+                let count = sqlite3_column_count(self.stmt());
 
                 for i in 0..count {
-                    let name = sqlite3_column_name(self.stmt, i);
+                    let name = sqlite3_column_name(self.stmt(), i);
 
-                    let (_, decltype, len) = colunm_decltype(self.stmt, i);
+                    let (mut column_type, decltype, len) = colunm_decltype(self.stmt(), i);
+
+                    // without a declared type, use the storage class of the current row
+                    if decltype.is_empty() && self.has_next {
+                        column_type = column_storage_class(self.stmt(), i);
+                    }
 
                     columns.push(driver::ColumnMetaData {
                         column_index: i as u64,
                         column_name: CStr::from_ptr(name).to_string_lossy().to_string(),
                         column_decltype: decltype,
                         column_decltype_len: len,
+                        column_type,
                     })
                 }
             };
@@ -434,13 +1314,15 @@ impl Rows {
     ) -> Result<rdbc::Value> {
         log::trace!(
             "{} :get column({:?},{:?})",
-            stmt_sql(self.stmt),
+            stmt_sql(self.stmt()),
             pos,
             column_type
         );
 
         let index = match pos {
-            driver::Placeholder::Index(index) => index as i32,
+            driver::Placeholder::Index(index) => {
+                i32::try_from(index).map_err(|_| Sqlite3Error::OutOfRange(index))?
+            }
             driver::Placeholder::Name(name) => {
                 let columns = self.colunms()?;
 
@@ -452,72 +1334,126 @@ impl Rows {
                 if let Some(index) = col {
                     index
                 } else {
-                    return Err(anyhow::Error::new(error::Sqlite3Error::UnknownColumn(name)));
+                    return Err(rdbc::Error::from(Sqlite3Error::UnknownColumn(name)));
                 }
             }
         };
 
-        let max_index = unsafe { sqlite3_column_count(self.stmt) };
+        let max_index = unsafe { sqlite3_column_count(self.stmt()) };
 
-        if index >= max_index {
-            return Err(anyhow::Error::new(error::Sqlite3Error::OutOfRange(
-                index as u64,
-            )));
+        if index < 0 || index >= max_index {
+            return Err(rdbc::Error::from(Sqlite3Error::OutOfRange(index as u64)));
         }
 
         if !self.has_next {
-            return Err(anyhow::Error::new(error::Sqlite3Error::NextDataError));
+            return Err(rdbc::Error::from(Sqlite3Error::NextDataError));
+        }
+
+        let column = self.column(index);
+
+        if unsafe { column.sqlite_type() } == SQLITE_NULL {
+            return Ok(driver::Value::Null);
         }
 
         let value = unsafe {
             match column_type {
-                driver::ColumnType::Bytes => {
-                    let len = sqlite3_column_bytes(self.stmt, index);
-                    let data = sqlite3_column_blob(self.stmt, index) as *const u8;
-                    let data = from_raw_parts(data, len as usize).to_owned();
+                driver::ColumnType::Bytes => driver::Value::Bytes(column.blob()),
+                driver::ColumnType::I64 => driver::Value::I64(column.int64()),
+                driver::ColumnType::F64 => driver::Value::F64(column.double()),
+                driver::ColumnType::String => driver::Value::String(column.text()),
+                driver::ColumnType::Null => driver::Value::Null,
+                column_type => {
+                    let storage_class = storage_class(column.sqlite_type());
 
-                    driver::Value::Bytes(data)
-                }
-                driver::ColumnType::I64 => {
-                    driver::Value::I64(sqlite3_column_int64(self.stmt, index))
-                }
-                driver::ColumnType::F64 => {
-                    driver::Value::F64(sqlite3_column_double(self.stmt, index))
-                }
-                driver::ColumnType::String => {
-                    let data = sqlite3_column_text(self.stmt, index) as *const i8;
+                    let value =
+                        self.get(driver::Placeholder::Index(index as u64), storage_class)?;
 
-                    driver::Value::String(CStr::from_ptr(data).to_string_lossy().to_string())
+                    return types::decode(column_type, value);
                 }
-                driver::ColumnType::Null => driver::Value::Null,
             }
         };
 
         Ok(value)
     }
 
+    /// Column `index` of the current row
+    fn column(&self, index: c_int) -> Column {
+        match &self.buffered {
+            Some(BufferedRows {
+                current: Some(row), ..
+            }) => Column::Value(row.0[index as usize]),
+            _ => Column::Stmt(self.stmt(), index),
+        }
+    }
+
+    /// Values of the current row, typed by their own storage class.
+    ///
+    /// sqlite3 columns are not typed, e.g. a `NUMERIC` or an expression column may
+    /// hold an integer in one row and a real in the next. Only the columns declared
+    /// with a type of [`types::decltype_column_type`] are decoded to that type.
+    pub fn get_row(&mut self) -> Result<Vec<rdbc::Value>> {
+        if !self.has_next {
+            return Err(rdbc::Error::from(Sqlite3Error::NextDataError));
+        }
+
+        let columns = self
+            .colunms()?
+            .iter()
+            .map(|column| (column.column_index, column.column_type))
+            .collect::<Vec<_>>();
+
+        columns
+            .into_iter()
+            .map(|(index, column_type)| {
+                let column_type = match column_type {
+                    driver::ColumnType::I64
+                    | driver::ColumnType::F64
+                    | driver::ColumnType::String
+                    | driver::ColumnType::Bytes => {
+                        storage_class(unsafe { self.column(index as c_int).sqlite_type() })
+                    }
+                    column_type => column_type,
+                };
+
+                self.get(driver::Placeholder::Index(index), column_type)
+            })
+            .collect()
+    }
+
+    /// Step and get up to `n` rows, see [`driver::Rows::next_batch`]
+    pub fn next_batch(&mut self, n: usize) -> Result<Vec<Vec<rdbc::Value>>> {
+        let mut rows = vec![];
+
+        while rows.len() < n && self.next()? {
+            rows.push(self.get_row()?);
+        }
+
+        Ok(rows)
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<bool> {
-        match unsafe { sqlite3_step(self.stmt) } {
-            SQLITE_DONE => {
-                self.has_next = false;
-                Ok(false)
-            }
+        if let Some(buffered) = &mut self.buffered {
+            buffered.current = buffered.rows.pop_front();
 
-            SQLITE_ROW => {
-                self.has_next = true;
-                Ok(true)
-            }
+            self.has_next = buffered.current.is_some();
 
-            rc => {
-                self.has_next = false;
-                Err(error::db_native_error(self.db, rc))
-            }
+            return Ok(self.has_next);
         }
+
+        let step = self.handle.step(self.timeout);
+
+        self.has_next = matches!(step, Ok(SQLITE_ROW));
+
+        step.map(|_| self.has_next)
     }
 }
 
 impl Drop for Rows {
     fn drop(&mut self) {
-        unsafe { sqlite3_reset(self.stmt) };
+        // buffered rows may be dropped after the statement is executed again
+        if self.buffered.is_none() {
+            unsafe { sqlite3_reset(self.stmt()) };
+        }
     }
 }
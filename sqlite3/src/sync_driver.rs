@@ -1,16 +1,20 @@
+use super::options::SqliteOptions;
 use super::sqlite3_rs;
 use rdbc::driver;
 
-pub struct SyncDriver {}
+#[derive(Default)]
+pub struct SyncDriver {
+    pub options: SqliteOptions,
+}
 
 impl driver::Driver for SyncDriver {
     fn open(&mut self, name: &str) -> driver::Connector {
         let (fut, waker) = driver::Connector::new();
 
-        waker
-            .lock()
-            .unwrap()
-            .ready(sqlite3_rs::Connection::open(name).map(|c| SyncConnection { inner: c }.into()));
+        waker.lock().unwrap().ready(
+            sqlite3_rs::Connection::open_with(name, &self.options)
+                .map(|c| SyncConnection { inner: c }.into()),
+        );
 
         fut
     }
@@ -20,21 +24,19 @@ struct SyncConnection {
     inner: sqlite3_rs::Connection,
 }
 
-unsafe impl Send for SyncConnection {}
-
-impl Into<Box<dyn driver::Connection>> for SyncConnection {
-    fn into(self) -> Box<dyn driver::Connection> {
-        Box::new(self)
+impl From<SyncConnection> for Box<dyn driver::Connection> {
+    fn from(val: SyncConnection) -> Self {
+        Box::new(val)
     }
 }
 
 impl driver::Connection for SyncConnection {
-    fn begin(&mut self) -> driver::Begin {
+    fn begin(&mut self, options: driver::TransactionOptions) -> driver::Begin {
         let (fut, waker) = driver::Begin::new();
 
         waker.lock().unwrap().ready(
             self.inner
-                .begin()
+                .begin(options)
                 .map(|tx| SyncTransaction { inner: tx }.into()),
         );
 
@@ -49,6 +51,22 @@ impl driver::Connection for SyncConnection {
         true
     }
 
+    fn ping(&mut self) -> driver::Ping {
+        let (fut, waker) = driver::Ping::new();
+
+        waker.lock().unwrap().ready(self.inner.ping());
+
+        fut
+    }
+
+    fn reset(&mut self) -> driver::Reset {
+        let (fut, waker) = driver::Reset::new();
+
+        waker.lock().unwrap().ready(self.inner.reset());
+
+        fut
+    }
+
     fn prepare(&mut self, query: &str) -> driver::Prepare {
         let (fut, waker) = driver::Prepare::new();
 
@@ -60,15 +78,26 @@ impl driver::Connection for SyncConnection {
 
         fut
     }
+
+    fn execute_script(&mut self, script: &str) -> driver::ExecuteScript {
+        let (fut, waker) = driver::ExecuteScript::new();
+
+        waker
+            .lock()
+            .unwrap()
+            .ready(self.inner.execute_script(script));
+
+        fut
+    }
 }
 
 struct SyncTransaction {
     inner: sqlite3_rs::Transaction,
 }
 
-impl Into<Box<dyn driver::Transaction>> for SyncTransaction {
-    fn into(self) -> Box<dyn driver::Transaction> {
-        Box::new(self)
+impl From<SyncTransaction> for Box<dyn driver::Transaction> {
+    fn from(val: SyncTransaction) -> Self {
+        Box::new(val)
     }
 }
 
@@ -93,27 +122,72 @@ impl driver::Transaction for SyncTransaction {
         fut
     }
 
+    fn execute_script(&mut self, script: &str) -> driver::ExecuteScript {
+        let (fut, waker) = driver::ExecuteScript::new();
+
+        waker
+            .lock()
+            .unwrap()
+            .ready(self.inner.execute_script(script));
+
+        fut
+    }
+
     fn rollback(&mut self) -> driver::Rollback {
         let (fut, waker) = driver::Rollback::new();
 
         waker.lock().unwrap().ready(self.inner.rollback());
         fut
     }
+
+    fn savepoint(&mut self, name: &str) -> driver::Savepoint {
+        let (fut, waker) = driver::Savepoint::new();
+
+        waker.lock().unwrap().ready(self.inner.savepoint(name));
+
+        fut
+    }
+
+    fn release(&mut self, name: &str) -> driver::Release {
+        let (fut, waker) = driver::Release::new();
+
+        waker.lock().unwrap().ready(self.inner.release(name));
+
+        fut
+    }
+
+    fn rollback_to(&mut self, name: &str) -> driver::RollbackTo {
+        let (fut, waker) = driver::RollbackTo::new();
+
+        waker.lock().unwrap().ready(self.inner.rollback_to(name));
+
+        fut
+    }
+
+    fn schema_changes(&self) -> std::sync::Arc<dyn driver::SchemaChanges> {
+        self.inner.schema_changes()
+    }
 }
 
 struct SyncStatement {
     inner: sqlite3_rs::Statement,
 }
 
-impl Into<Box<dyn driver::Statement>> for SyncStatement {
-    fn into(self) -> Box<dyn driver::Statement> {
-        Box::new(self)
+impl From<SyncStatement> for Box<dyn driver::Statement> {
+    fn from(val: SyncStatement) -> Self {
+        Box::new(val)
     }
 }
 
-unsafe impl Send for SyncStatement {}
-
 impl driver::Statement for SyncStatement {
+    fn set_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn cancel_handle(&self) -> std::sync::Arc<dyn driver::CancelHandle> {
+        std::sync::Arc::new(self.inner.interrupt_handle())
+    }
+
     fn execute(&mut self, args: Vec<rdbc::Arg>) -> driver::Execute {
         let (fut, waker) = driver::Execute::new();
 
@@ -122,10 +196,30 @@ impl driver::Statement for SyncStatement {
         fut
     }
 
+    fn execute_batch(&mut self, batch: Vec<Vec<rdbc::Arg>>) -> driver::ExecuteBatch {
+        let (fut, waker) = driver::ExecuteBatch::new();
+
+        waker.lock().unwrap().ready(self.inner.execute_batch(batch));
+
+        fut
+    }
+
     fn num_input(&self) -> Option<u32> {
         self.inner.num_input()
     }
 
+    fn execute_returning(&mut self, args: Vec<rdbc::Arg>) -> driver::ExecuteReturning {
+        let (fut, waker) = driver::ExecuteReturning::new();
+
+        waker.lock().unwrap().ready(
+            self.inner
+                .execute_returning(args)
+                .map(|(result, rows)| (result, SyncRows { inner: rows }.into())),
+        );
+
+        fut
+    }
+
     fn query(&mut self, args: Vec<rdbc::Arg>) -> driver::Query {
         let (fut, waker) = driver::Query::new();
 
@@ -143,22 +237,17 @@ struct SyncRows {
     inner: sqlite3_rs::Rows,
 }
 
-impl Into<Box<dyn driver::Rows>> for SyncRows {
-    fn into(self) -> Box<dyn driver::Rows> {
-        Box::new(self)
+impl From<SyncRows> for Box<dyn driver::Rows> {
+    fn from(val: SyncRows) -> Self {
+        Box::new(val)
     }
 }
 
-unsafe impl Send for SyncRows {}
-
 impl driver::Rows for SyncRows {
     fn colunms(&mut self) -> driver::Columns {
         let (fut, waker) = driver::Columns::new();
 
-        waker
-            .lock()
-            .unwrap()
-            .ready(self.inner.colunms().map(|c| c.clone()));
+        waker.lock().unwrap().ready(self.inner.colunms().cloned());
 
         fut
     }
@@ -185,4 +274,20 @@ impl driver::Rows for SyncRows {
 
         fut
     }
+
+    fn get_row(&mut self) -> driver::RowsGetRow {
+        let (fut, waker) = driver::RowsGetRow::new();
+
+        waker.lock().unwrap().ready(self.inner.get_row());
+
+        fut
+    }
+
+    fn next_batch(&mut self, n: usize) -> driver::RowsNextBatch {
+        let (fut, waker) = driver::RowsNextBatch::new();
+
+        waker.lock().unwrap().ready(self.inner.next_batch(n));
+
+        fut
+    }
 }
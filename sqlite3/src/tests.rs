@@ -1,5 +1,5 @@
 use std::{
-    fs::{create_dir_all, remove_dir_all},
+    fs::{create_dir_all, remove_dir_all, remove_file},
     path::PathBuf,
 };
 
@@ -12,14 +12,29 @@ fn create_test_dir() -> PathBuf {
     let path: PathBuf = ".test".into();
 
     if path.exists() {
-        remove_dir_all(path.to_owned()).unwrap();
+        remove_dir_all(&path).unwrap();
     }
 
-    create_dir_all(path.to_owned()).unwrap();
+    create_dir_all(&path).unwrap();
 
     path
 }
 
+/// Returns the uri of a fresh database file `.test/{name}.db`
+fn test_db_file(name: &str) -> String {
+    let path: PathBuf = ".test".into();
+
+    create_dir_all(&path).unwrap();
+
+    let path = path.join(format!("{}.db", name));
+
+    if path.exists() {
+        remove_file(&path).unwrap();
+    }
+
+    format!("file:{}", path.to_string_lossy())
+}
+
 #[async_std::test]
 async fn test_create_table() {
     _ = pretty_env_logger::try_init();
@@ -92,18 +107,21 @@ async fn test_stmt() {
                 column_name: "x".to_owned(),
                 column_decltype: "INTEGER".to_owned(),
                 column_decltype_len: Some(8),
+                column_type: ColumnType::I64,
             },
             ColumnMetaData {
                 column_index: 1,
                 column_name: "y".to_owned(),
                 column_decltype: "TEXT".to_owned(),
                 column_decltype_len: None,
+                column_type: ColumnType::String,
             },
             ColumnMetaData {
                 column_index: 2,
                 column_name: "z".to_owned(),
                 column_decltype: "NUMERIC".to_owned(),
                 column_decltype_len: None,
+                column_type: ColumnType::String,
             }
         ]
     );
@@ -322,18 +340,21 @@ async fn test_tx_commit_data() {
                     column_name: "x".to_owned(),
                     column_decltype: "INTEGER".to_owned(),
                     column_decltype_len: Some(8),
+                    column_type: ColumnType::I64,
                 },
                 ColumnMetaData {
                     column_index: 1,
                     column_name: "y".to_owned(),
                     column_decltype: "TEXT".to_owned(),
                     column_decltype_len: None,
+                    column_type: ColumnType::String,
                 },
                 ColumnMetaData {
                     column_index: 2,
                     column_name: "z".to_owned(),
                     column_decltype: "NUMERIC".to_owned(),
                     column_decltype_len: None,
+                    column_type: ColumnType::String,
                 }
             ]
         );
@@ -382,18 +403,21 @@ async fn test_tx_commit_data() {
                 column_name: "x".to_owned(),
                 column_decltype: "INTEGER".to_owned(),
                 column_decltype_len: Some(8),
+                column_type: ColumnType::I64,
             },
             ColumnMetaData {
                 column_index: 1,
                 column_name: "y".to_owned(),
                 column_decltype: "TEXT".to_owned(),
                 column_decltype_len: None,
+                column_type: ColumnType::String,
             },
             ColumnMetaData {
                 column_index: 2,
                 column_name: "z".to_owned(),
                 column_decltype: "NUMERIC".to_owned(),
                 column_decltype_len: None,
+                column_type: ColumnType::String,
             }
         ]
     );
@@ -436,7 +460,7 @@ async fn test_tx_rollback_data() {
     let mut db = open("sqlite3", "file:memdb_rollback?mode=memory&cache=shared").unwrap();
 
     {
-        let mut tx = db.begin().await.unwrap();
+        let tx = db.begin().await.unwrap();
 
         let mut stmt = tx
             .prepare("CREATE TABLE t(x INTEGER PRIMARY KEY ASC, y TEXT, z NUMERIC);")
@@ -482,18 +506,21 @@ async fn test_tx_rollback_data() {
                     column_name: "x".to_owned(),
                     column_decltype: "INTEGER".to_owned(),
                     column_decltype_len: Some(8),
+                    column_type: ColumnType::I64,
                 },
                 ColumnMetaData {
                     column_index: 1,
                     column_name: "y".to_owned(),
                     column_decltype: "TEXT".to_owned(),
                     column_decltype_len: None,
+                    column_type: ColumnType::String,
                 },
                 ColumnMetaData {
                     column_index: 2,
                     column_name: "z".to_owned(),
                     column_decltype: "NUMERIC".to_owned(),
                     column_decltype_len: None,
+                    column_type: ColumnType::String,
                 }
             ]
         );
@@ -527,3 +554,2874 @@ async fn test_tx_rollback_data() {
     // no such table: t
     assert!(db.prepare("SELECT * FROM t").await.is_err());
 }
+
+#[async_std::test]
+async fn test_pool_max_open_connections() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open_with(
+        "sqlite3",
+        "file:memdb_pool_max_open?mode=memory&cache=shared",
+        PoolOptions {
+            max_open_connections: 2,
+            acquire_timeout: Some(std::time::Duration::from_millis(100)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let stmt1 = db.prepare("SELECT 1").await.unwrap();
+    let stmt2 = db.prepare("SELECT 1").await.unwrap();
+
+    assert_eq!(db.pool_status().open_connections, 2);
+
+    let err = db.prepare("SELECT 1").await.err().unwrap();
+
+    assert!(matches!(
+        err.downcast_ref::<PoolError>(),
+        Some(PoolError::AcquireTimeout(_))
+    ));
+
+    assert_eq!(db.pool_status().waiters, 0);
+
+    drop(stmt1);
+
+    assert_eq!(
+        db.pool_status(),
+        PoolStatus {
+            open_connections: 2,
+            idle_connections: 1,
+            waiters: 0,
+            reset_connections: 0,
+        }
+    );
+
+    let _stmt3 = db.prepare("SELECT 1").await.unwrap();
+
+    drop(stmt2);
+
+    assert_eq!(db.pool_status().open_connections, 2);
+    assert_eq!(db.pool_status().idle_connections, 1);
+}
+
+#[async_std::test]
+async fn test_pool_fifo_waiters() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let db = open_with(
+        "sqlite3",
+        "file:memdb_pool_fifo?mode=memory&cache=shared",
+        PoolOptions {
+            max_open_connections: 1,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let stmt = db.clone().prepare("SELECT 1").await.unwrap();
+
+    let order = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+    let mut tasks = vec![];
+
+    for i in 0..3 {
+        let mut task_db = db.clone();
+        let order = order.clone();
+
+        tasks.push(async_std::task::spawn(async move {
+            let _stmt = task_db.prepare("SELECT 1").await.unwrap();
+
+            order.lock().unwrap().push(i);
+        }));
+
+        // make sure waiters are registered in order
+        while db.pool_status().waiters <= i {
+            async_std::task::sleep(std::time::Duration::from_millis(1)).await;
+        }
+    }
+
+    drop(stmt);
+
+    for task in tasks {
+        task.await;
+    }
+
+    assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    assert_eq!(db.pool_status().open_connections, 1);
+}
+
+#[async_std::test]
+async fn test_pool_cancel_acquire() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open_with(
+        "sqlite3",
+        "file:memdb_pool_cancel?mode=memory&cache=shared",
+        PoolOptions {
+            max_open_connections: 1,
+            acquire_timeout: None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let stmt = db.prepare("SELECT 1").await.unwrap();
+
+    let timeout = async_std::future::timeout(
+        std::time::Duration::from_millis(50),
+        db.clone().prepare("SELECT 1"),
+    )
+    .await;
+
+    assert!(timeout.is_err());
+
+    assert_eq!(db.pool_status().waiters, 0);
+
+    drop(stmt);
+
+    db.prepare("SELECT 1").await.unwrap();
+}
+
+#[async_std::test]
+async fn test_pool_idle_timeout() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open_with(
+        "sqlite3",
+        "file:memdb_pool_idle_timeout?mode=memory&cache=shared",
+        PoolOptions {
+            min_idle_connections: 1,
+            idle_timeout: Some(std::time::Duration::from_millis(20)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let stmt1 = db.prepare("SELECT 1").await.unwrap();
+    let stmt2 = db.prepare("SELECT 1").await.unwrap();
+
+    drop(stmt1);
+    drop(stmt2);
+
+    assert!(db.pool_status().idle_connections >= 2);
+
+    async_std::task::sleep(std::time::Duration::from_millis(200)).await;
+
+    // idle connections are closed down to min_idle_connections
+    assert_eq!(
+        db.pool_status(),
+        PoolStatus {
+            open_connections: 1,
+            idle_connections: 1,
+            waiters: 0,
+            reset_connections: 0,
+        }
+    );
+}
+
+async fn query_after_db_file_removed(
+    name: &str,
+    validation: Validation,
+) -> rdbc::Result<Statement<'static>> {
+    let uri = test_db_file(name);
+
+    let mut db = open_with(
+        "sqlite3",
+        &uri,
+        PoolOptions {
+            max_open_connections: 1,
+            validation,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut stmt = db
+        .prepare("CREATE TABLE t(x INTEGER PRIMARY KEY ASC, y TEXT, z NUMERIC);")
+        .await
+        .unwrap();
+
+    stmt.execute(vec![]).await.unwrap();
+
+    drop(stmt);
+
+    remove_file(uri.trim_start_matches("file:")).unwrap();
+
+    db.prepare("SELECT * FROM t").await
+}
+
+#[async_std::test]
+async fn test_pool_validation() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    // the idle connection still sees the deleted database file.
+    assert!(
+        query_after_db_file_removed("pool_validation_never", Validation::Never)
+            .await
+            .is_ok()
+    );
+
+    // the moved connection is evicted, the new connection opens an empty database.
+    assert!(
+        query_after_db_file_removed("pool_validation_always", Validation::Always)
+            .await
+            .is_err()
+    );
+}
+
+#[async_std::test]
+async fn test_pool_reset_connection() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open_with(
+        "sqlite3",
+        "file:memdb_pool_reset?mode=memory&cache=shared",
+        PoolOptions {
+            max_open_connections: 1,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    db.prepare("BEGIN")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    // the open transaction is rolled back before reuse
+    db.prepare("CREATE TEMP TABLE tmp(x INTEGER)")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(db.pool_status().reset_connections, 1);
+
+    // the temp table is dropped before reuse
+    db.prepare("PRAGMA foreign_keys = ON")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(db.pool_status().reset_connections, 2);
+
+    // the pragma is restored before reuse
+    let mut stmt = db
+        .prepare("SELECT * FROM pragma_foreign_keys()")
+        .await
+        .unwrap();
+
+    assert_eq!(db.pool_status().reset_connections, 3);
+
+    let mut rows = stmt.query(vec![]).await.unwrap();
+
+    assert!(rows.next().await.unwrap());
+
+    assert_eq!(
+        rows.get(0, driver::ColumnType::I64).await.unwrap(),
+        rdbc::Value::I64(0)
+    );
+
+    drop(rows);
+    drop(stmt);
+
+    assert!(db.prepare("SELECT * FROM temp.tmp").await.is_err());
+
+    assert_eq!(db.pool_status().reset_connections, 3);
+
+    // the cached statement is not prepared again, running it still needs a reset
+    db.prepare("PRAGMA foreign_keys = ON")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    db.prepare("ATTACH DATABASE ':memory:' AS aux")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(db.pool_status().reset_connections, 4);
+
+    // the attached database is detached before reuse
+    assert!(db.prepare("SELECT * FROM aux.sqlite_master").await.is_err());
+
+    assert_eq!(db.pool_status().reset_connections, 5);
+}
+
+async fn rows_test_db(name: &str) -> Database {
+    let mut db = open("sqlite3", &test_db_file(name)).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY ASC, y TEXT, z REAL);")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    db.prepare("INSERT INTO t(y,z) VALUES('hello', 1.5), (NULL, NULL), ('world', 2.5);")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    db
+}
+
+#[async_std::test]
+async fn test_rows_stream() {
+    use futures::TryStreamExt;
+
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = rows_test_db("rows_stream").await;
+
+    let mut stmt = db
+        .prepare("SELECT x, y, z, x * 2 AS w FROM t ORDER BY x")
+        .await
+        .unwrap();
+
+    let mut rows = stmt.query(vec![]).await.unwrap();
+
+    let row = rows.try_next().await.unwrap().unwrap();
+
+    assert_eq!(row.len(), 4);
+    assert_eq!(row.get(0), Some(&Value::I64(1)));
+    assert_eq!(row.get("y"), Some(&Value::String("hello".to_owned())));
+    assert_eq!(row.get("z"), Some(&Value::F64(1.5)));
+    // expression columns use the storage class of the first row
+    assert_eq!(row.columns()[3].column_type, ColumnType::I64);
+    assert_eq!(row.get("w"), Some(&Value::I64(2)));
+    assert_eq!(row.get("unknown"), None);
+
+    let row = rows.try_next().await.unwrap().unwrap();
+
+    assert_eq!(
+        row.values(),
+        &[Value::I64(2), Value::Null, Value::Null, Value::I64(4)]
+    );
+
+    let row = rows.try_next().await.unwrap().unwrap();
+
+    assert_eq!(row.get(1), Some(&Value::String("world".to_owned())));
+
+    assert!(rows.try_next().await.unwrap().is_none());
+    assert!(rows.try_next().await.unwrap().is_none());
+}
+
+#[async_std::test]
+async fn test_rows_storage_class() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("rows_storage_class")).unwrap();
+
+    db.execute_script(
+        "CREATE TABLE n(x INTEGER PRIMARY KEY, v NUMERIC);
+        INSERT INTO n(v) VALUES(1), (2.5), ('abc'), (NULL);",
+    )
+    .await
+    .unwrap();
+
+    // values are typed by their own storage class, not by the column declared
+    // type or the first row
+    let rows = db
+        .prepare("SELECT v, CASE WHEN x = 1 THEN x ELSE v END AS e FROM n ORDER BY x")
+        .await
+        .unwrap()
+        .query(())
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows.into_iter().map(Row::into_values).collect::<Vec<_>>(),
+        vec![
+            vec![Value::I64(1), Value::I64(1)],
+            vec![Value::F64(2.5), Value::F64(2.5)],
+            vec![
+                Value::String("abc".to_owned()),
+                Value::String("abc".to_owned())
+            ],
+            vec![Value::Null, Value::Null],
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_rows_fetch() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = rows_test_db("rows_fetch").await;
+
+    let mut stmt = db.prepare("SELECT y FROM t ORDER BY x").await.unwrap();
+
+    let rows = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows.into_iter()
+            .flat_map(Row::into_values)
+            .collect::<Vec<_>>(),
+        vec![
+            Value::String("hello".to_owned()),
+            Value::Null,
+            Value::String("world".to_owned())
+        ]
+    );
+
+    // the first of several rows
+    let row = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(row.get("y"), Some(&Value::String("hello".to_owned())));
+
+    let mut stmt = db.prepare("SELECT y FROM t WHERE x > 3").await.unwrap();
+
+    assert!(stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        stmt.query(vec![])
+            .await
+            .unwrap()
+            .fetch_optional::<Row>()
+            .await
+            .unwrap(),
+        None
+    );
+
+    let err = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<RowsError>(),
+        Some(RowsError::NoRows)
+    ));
+}
+
+#[derive(Debug, PartialEq, rdbc_attributes::FromRow)]
+struct Item {
+    #[col_name(x)]
+    id: i64,
+    y: Option<String>,
+    #[col_name("z")]
+    #[col_default(-1.0)]
+    price: f64,
+    #[col_default]
+    missing: String,
+}
+
+#[derive(Debug, PartialEq, rdbc_attributes::FromRow)]
+struct ItemTuple(i64, Option<String>);
+
+#[async_std::test]
+async fn test_rows_from_row() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = rows_test_db("rows_from_row").await;
+
+    let mut stmt = db.prepare("SELECT * FROM t ORDER BY x").await.unwrap();
+
+    let items = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_all::<Item>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        items,
+        vec![
+            Item {
+                id: 1,
+                y: Some("hello".to_owned()),
+                price: 1.5,
+                missing: "".to_owned(),
+            },
+            Item {
+                id: 2,
+                y: None,
+                price: -1.0,
+                missing: "".to_owned(),
+            },
+            Item {
+                id: 3,
+                y: Some("world".to_owned()),
+                price: 2.5,
+                missing: "".to_owned(),
+            },
+        ]
+    );
+
+    let mut stmt = db
+        .prepare("SELECT x, y FROM t ORDER BY x DESC")
+        .await
+        .unwrap();
+
+    let item = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_one::<ItemTuple>()
+        .await
+        .unwrap();
+
+    assert_eq!(item, ItemTuple(3, Some("world".to_owned())));
+
+    // `id` is not nullable
+    let mut stmt = db.prepare("SELECT NULL AS x, y FROM t").await.unwrap();
+
+    let err = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_one::<Item>()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ValueError>(),
+        Some(ValueError::TypeMismatch { .. })
+    ));
+
+    // `y` is missing
+    let mut stmt = db.prepare("SELECT x FROM t").await.unwrap();
+
+    let err = stmt
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_one::<Item>()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<RowsError>(),
+        Some(RowsError::UnknownColumn(name)) if name == "y"
+    ));
+}
+
+#[async_std::test]
+async fn test_value_conversions() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("value_conversions")).unwrap();
+
+    db.prepare("CREATE TABLE t(a INTEGER, b INTEGER, c REAL, d TEXT, e BLOB, f INTEGER);")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    let values = [
+        200u8.to_value().unwrap(),
+        true.to_value().unwrap(),
+        1.5f32.to_value().unwrap(),
+        "hello".to_value().unwrap(),
+        b"\x00\x01".as_slice().to_value().unwrap(),
+        None::<i32>.to_value().unwrap(),
+    ];
+
+    db.prepare("INSERT INTO t VALUES(?, ?, ?, ?, ?, ?);")
+        .await
+        .unwrap()
+        .execute(values)
+        .await
+        .unwrap();
+
+    let row = db
+        .prepare("SELECT * FROM t")
+        .await
+        .unwrap()
+        .query(vec![])
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(row.try_get::<u8, _>("a").unwrap(), 200);
+    assert_eq!(row.try_get::<i16, _>("a").unwrap(), 200);
+    assert!(row.try_get::<bool, _>("b").unwrap());
+    assert_eq!(row.try_get::<f32, _>("c").unwrap(), 1.5);
+    assert_eq!(row.try_get::<String, _>("d").unwrap(), "hello");
+    assert_eq!(row.try_get::<Vec<u8>, _>("e").unwrap(), vec![0, 1]);
+    assert_eq!(row.try_get::<Option<u64>, _>("f").unwrap(), None);
+
+    // checked conversions
+    for err in [
+        row.try_get::<i8, _>("a").unwrap_err(),
+        row.try_get::<bool, _>("a").unwrap_err(),
+        u64::MAX.to_value().unwrap_err(),
+        // would be rounded
+        f64::from_value(&Value::I64(i64::MAX)).unwrap_err(),
+        f64::from_value(&Value::I64(-(1 << 53) - 1)).unwrap_err(),
+    ] {
+        assert!(matches!(
+            err.downcast_ref::<ValueError>(),
+            Some(ValueError::OutOfRange(..))
+        ));
+    }
+
+    assert_eq!(
+        f64::from_value(&Value::I64(1 << 53)).unwrap(),
+        2f64.powi(53)
+    );
+    assert_eq!(row.try_get::<f64, _>("a").unwrap(), 200.0);
+
+    assert!(matches!(
+        row.try_get::<u64, _>("f")
+            .unwrap_err()
+            .downcast_ref::<ValueError>(),
+        Some(ValueError::TypeMismatch { .. })
+    ));
+}
+
+#[async_std::test]
+async fn test_params() {
+    use std::collections::HashMap;
+
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("params")).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER, y TEXT, z REAL);")
+        .await
+        .unwrap()
+        .execute(params![])
+        .await
+        .unwrap();
+
+    let mut stmt = db.prepare("INSERT INTO t VALUES(?, ?, ?);").await.unwrap();
+
+    let y = "world".to_owned();
+
+    stmt.execute(params![1, "hello", 1.5]).await.unwrap();
+    stmt.execute((2, &y, None::<f64>)).await.unwrap();
+    stmt.execute([3, 4, 5]).await.unwrap();
+
+    let mut stmt = db
+        .prepare("INSERT INTO t VALUES(:x, :y, :z);")
+        .await
+        .unwrap();
+
+    stmt.execute(named_params! { ":x" => 6, ":y" => y, ":z" => 2.5 })
+        .await
+        .unwrap();
+
+    stmt.execute(HashMap::from([
+        (":x".to_owned(), Value::I64(7)),
+        (":y".to_owned(), Value::Null),
+        (":z".to_owned(), Value::Null),
+    ]))
+    .await
+    .unwrap();
+
+    // out of range values are reported before binding
+    let err = stmt
+        .execute(named_params! { ":x" => u64::MAX, ":y" => "", ":z" => 0.0 })
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ValueError>(),
+        Some(ValueError::OutOfRange(..))
+    ));
+
+    let mut stmt = db
+        .prepare("SELECT x, y, z FROM t WHERE x >= ? ORDER BY x")
+        .await
+        .unwrap();
+
+    let rows = stmt
+        .query(params![2])
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows.into_iter().map(Row::into_values).collect::<Vec<_>>(),
+        vec![
+            vec![
+                Value::I64(2),
+                Value::String("world".to_owned()),
+                Value::Null
+            ],
+            vec![
+                Value::I64(3),
+                Value::String("4".to_owned()),
+                Value::F64(5.0)
+            ],
+            vec![
+                Value::I64(6),
+                Value::String("world".to_owned()),
+                Value::F64(2.5)
+            ],
+            vec![Value::I64(7), Value::Null, Value::Null],
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_bool_value() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("bool_value")).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY, b BOOLEAN);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut stmt = db.prepare("INSERT INTO t(b) VALUES(?);").await.unwrap();
+
+    stmt.execute(params![true]).await.unwrap();
+    stmt.execute(params![false]).await.unwrap();
+    stmt.execute(params![None::<bool>]).await.unwrap();
+
+    let mut stmt = db
+        .prepare("SELECT b, typeof(b) AS storage FROM t ORDER BY x")
+        .await
+        .unwrap();
+
+    let rows = stmt
+        .query(())
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].columns()[0].column_type, ColumnType::Bool);
+
+    assert_eq!(
+        rows.into_iter().map(Row::into_values).collect::<Vec<_>>(),
+        vec![
+            vec![Value::Bool(true), Value::String("integer".to_owned())],
+            vec![Value::Bool(false), Value::String("integer".to_owned())],
+            vec![Value::Null, Value::String("null".to_owned())],
+        ]
+    );
+}
+
+#[cfg(all(
+    feature = "chrono",
+    feature = "decimal",
+    feature = "uuid",
+    feature = "json"
+))]
+#[async_std::test]
+async fn test_rich_values() {
+    use chrono::Timelike;
+
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("rich_values")).unwrap();
+
+    db.prepare(
+        "CREATE TABLE t(x INTEGER PRIMARY KEY, d DATE, t TIME, dt DATETIME, ts TIMESTAMP, \
+         n DECIMAL(20, 4), u UUID, j JSON);",
+    )
+    .await
+    .unwrap()
+    .execute(())
+    .await
+    .unwrap();
+
+    let date = chrono::NaiveDate::from_ymd_opt(2022, 7, 31).unwrap();
+    let time = chrono::NaiveTime::from_hms_milli_opt(12, 30, 59, 123).unwrap();
+    let datetime = date.and_time(time);
+    let timestamp = datetime.and_utc();
+    let decimal: rust_decimal::Decimal = "1234567890.1234".parse().unwrap();
+    let uuid = uuid::Uuid::new_v4();
+    let json = serde_json::json!({ "name": "rdbc", "tags": [1, 2] });
+
+    db.prepare("INSERT INTO t(d, t, dt, ts, n, u, j) VALUES(?, ?, ?, ?, ?, ?, ?);")
+        .await
+        .unwrap()
+        .execute(params![
+            date, time, datetime, timestamp, decimal, uuid, json
+        ])
+        .await
+        .unwrap();
+
+    let row = db
+        .prepare("SELECT d, t, dt, ts, n, u, j FROM t")
+        .await
+        .unwrap()
+        .query(())
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(row.try_get::<chrono::NaiveDate, _>("d").unwrap(), date);
+    assert_eq!(row.try_get::<chrono::NaiveTime, _>("t").unwrap(), time);
+    assert_eq!(
+        row.try_get::<chrono::NaiveDateTime, _>("dt").unwrap(),
+        datetime
+    );
+    assert_eq!(
+        row.try_get::<chrono::DateTime<chrono::Utc>, _>("ts")
+            .unwrap(),
+        timestamp
+    );
+    assert_eq!(
+        row.try_get::<rust_decimal::Decimal, _>("n").unwrap(),
+        decimal
+    );
+    assert_eq!(row.try_get::<uuid::Uuid, _>("u").unwrap(), uuid);
+    assert_eq!(row.try_get::<serde_json::Value, _>("j").unwrap(), json);
+
+    // sqlite date functions understand the TEXT encoding, and unix time INTEGER
+    // or julian day REAL values can be loaded too
+    let row = db
+        .prepare("SELECT date(dt) AS d FROM t")
+        .await
+        .unwrap()
+        .query(())
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(row.get("d"), Some(&Value::String("2022-07-31".to_owned())));
+
+    let mut stmt = db
+        .prepare("SELECT CAST(strftime('%s', ts) AS INTEGER), julianday(ts) FROM t")
+        .await
+        .unwrap();
+
+    let mut rows = stmt.query(()).await.unwrap();
+
+    assert!(rows.next().await.unwrap());
+
+    assert_eq!(
+        rows.get(0, ColumnType::Timestamp).await.unwrap(),
+        Value::Timestamp(datetime.with_nanosecond(0).unwrap().and_utc())
+    );
+
+    assert_eq!(
+        rows.get(1, ColumnType::Timestamp).await.unwrap(),
+        Value::Timestamp(timestamp)
+    );
+}
+
+#[async_std::test]
+async fn test_error_kind() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let uri = test_db_file("error_kind");
+
+    let mut db = open("sqlite3", &uri).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY, y TEXT UNIQUE);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut stmt = db.prepare("INSERT INTO t(y) VALUES(?);").await.unwrap();
+
+    stmt.execute(params!["hello"]).await.unwrap();
+
+    let err = stmt.execute(params!["hello"]).await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Constraint);
+    assert_eq!(
+        err.downcast_ref::<error::Sqlite3Error>().unwrap().code(),
+        Some(sqlite3_sys::SQLITE_CONSTRAINT_UNIQUE)
+    );
+
+    let err = db.prepare("SELEC * FROM t").await.err().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Syntax);
+
+    let err = db.prepare("SELECT * FROM unknown").await.err().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Syntax);
+
+    // reporters walking the source chain print each message once
+    let err = err.context("load unknown");
+
+    let chain = std::iter::successors(Some(&err as &(dyn std::error::Error + 'static)), |err| {
+        err.source()
+    })
+    .map(|err| err.to_string())
+    .collect::<Vec<_>>();
+
+    assert_eq!(chain.len(), 2, "{:?}", chain);
+    assert_eq!(chain[0], "load unknown");
+    assert_eq!(
+        chain
+            .iter()
+            .filter(|msg| msg.contains("no such table"))
+            .count(),
+        1,
+        "{:?}",
+        chain
+    );
+
+    // SQLITE_ERROR, but not an invalid SQL
+    let err = db
+        .execute_script("BEGIN; BEGIN; ROLLBACK;")
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert_eq!(
+        err.downcast_ref::<error::Sqlite3Error>().unwrap().code(),
+        Some(sqlite3_sys::SQLITE_ERROR)
+    );
+
+    let mut stmt = db.prepare("SELECT y FROM t WHERE x > 1").await.unwrap();
+
+    let err = stmt
+        .query(())
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let err = params![u64::MAX].into_args().unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::TypeMismatch);
+
+    // the write lock is held by the open transaction
+    let mut tx = db.begin().await.unwrap();
+
+    tx.prepare("INSERT INTO t(y) VALUES('world');")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut other = open("sqlite3", &uri).unwrap();
+
+    let err = other
+        .prepare("INSERT INTO t(y) VALUES('busy');")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Busy);
+
+    tx.rollback().await.unwrap();
+
+    let mut db = open("sqlite3", "file:.test/not/exists.db").unwrap();
+
+    let err = db.prepare("SELECT 1").await.err().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Connection);
+}
+
+#[async_std::test]
+async fn test_tx_savepoint() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("tx_savepoint")).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut tx = db.begin().await.unwrap();
+
+    let mut insert = tx.prepare("INSERT INTO t VALUES(?);").await.unwrap();
+
+    insert.execute(params![1]).await.unwrap();
+
+    tx.savepoint("a").await.unwrap();
+
+    insert.execute(params![2]).await.unwrap();
+
+    tx.savepoint("b \"quoted\"").await.unwrap();
+
+    insert.execute(params![3]).await.unwrap();
+
+    tx.rollback_to("b \"quoted\"").await.unwrap();
+
+    insert.execute(params![4]).await.unwrap();
+
+    tx.release("a").await.unwrap();
+
+    assert_eq!(
+        tx.rollback_to("a").await.unwrap_err().kind(),
+        ErrorKind::Syntax
+    );
+
+    drop(insert);
+
+    tx.commit().await.unwrap();
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 4]);
+}
+
+async fn select_x(db: &mut Database) -> Vec<i64> {
+    let mut stmt = db.prepare("SELECT x FROM t ORDER BY x").await.unwrap();
+
+    stmt.query(())
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.try_get(0).unwrap())
+        .collect()
+}
+
+async fn insert_x<'a>(tx: &mut NestedTransaction<'a>, x: i64) {
+    tx.prepare("INSERT INTO t VALUES(?);")
+        .await
+        .unwrap()
+        .execute(params![x])
+        .await
+        .unwrap();
+}
+
+#[async_std::test]
+async fn test_tx_nested() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("tx_nested")).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut tx = db.begin().await.unwrap();
+
+    {
+        let mut nested = tx.begin_nested().await.unwrap();
+
+        insert_x(&mut nested, 1).await;
+
+        {
+            let mut inner = nested.begin_nested().await.unwrap();
+
+            insert_x(&mut inner, 2).await;
+
+            inner.rollback().await.unwrap();
+        }
+
+        {
+            let mut inner = nested.begin_nested().await.unwrap();
+
+            insert_x(&mut inner, 3).await;
+
+            inner.commit().await.unwrap();
+        }
+
+        nested.commit().await.unwrap();
+    }
+
+    {
+        let mut nested = tx.begin_nested().await.unwrap();
+
+        insert_x(&mut nested, 4).await;
+
+        // dropped without commit
+    }
+
+    tx.commit().await.unwrap();
+
+    drop(tx);
+
+    assert_eq!(select_x(&mut db).await, vec![1, 3]);
+}
+
+#[async_std::test]
+async fn test_tx_options() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let uri = test_db_file("tx_options");
+
+    let mut db = open("sqlite3", &uri).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let immediate = TransactionOptions {
+        locking_mode: LockingMode::Immediate,
+        ..Default::default()
+    };
+
+    let mut tx = db.begin_with(immediate.clone()).await.unwrap();
+
+    let mut other = open("sqlite3", &uri).unwrap();
+
+    // the write lock is taken by begin, not by the first write
+    assert_eq!(
+        other
+            .begin_with(immediate.clone())
+            .await
+            .map(|_| ())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::Busy
+    );
+
+    // readers are not blocked
+    let mut deferred = other.begin().await.unwrap();
+
+    assert_eq!(select_x_tx(&mut deferred).await, Vec::<i64>::new());
+
+    deferred.rollback().await.unwrap();
+
+    tx.rollback().await.unwrap();
+
+    let mut tx = db
+        .begin_with(TransactionOptions {
+            locking_mode: LockingMode::Exclusive,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // readers are blocked too
+    let read = async {
+        let mut stmt = other.prepare("SELECT x FROM t").await?;
+
+        stmt.query(()).await.map(|_| ())
+    };
+
+    assert_eq!(read.await.unwrap_err().kind(), ErrorKind::Busy);
+
+    tx.rollback().await.unwrap();
+
+    let mut tx = db
+        .begin_with(TransactionOptions {
+            read_only: true,
+            isolation_level: Some(IsolationLevel::Serializable),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(select_x_tx(&mut tx).await, Vec::<i64>::new());
+
+    assert!(tx
+        .prepare("INSERT INTO t VALUES(1);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .is_err());
+
+    tx.commit().await.unwrap();
+
+    drop(tx);
+
+    // query_only is restored when the read only transaction finishes
+    let mut tx = db.begin().await.unwrap();
+
+    tx.prepare("INSERT INTO t VALUES(1);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    tx.commit().await.unwrap();
+
+    assert_eq!(select_x(&mut db).await, vec![1]);
+}
+
+#[async_std::test]
+async fn test_tx_commit_busy() {
+    use rdbc::driver::Driver;
+
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let uri = test_db_file("tx_commit_busy");
+
+    let mut db = open("sqlite3", &uri).unwrap();
+
+    db.execute_script("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap();
+
+    let mut other = open("sqlite3", &uri).unwrap();
+
+    // the shared lock of the reader blocks the commit of the writer
+    let mut reader = other.begin().await.unwrap();
+
+    assert_eq!(select_x_tx(&mut reader).await, Vec::<i64>::new());
+
+    let mut conn = sync_driver::SyncDriver::default().open(&uri).await.unwrap();
+
+    let mut tx = conn.begin(Default::default()).await.unwrap();
+
+    tx.prepare("INSERT INTO t VALUES(1);")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    assert_eq!(tx.commit().await.unwrap_err().kind(), ErrorKind::Busy);
+
+    // the transaction is still open, dropping it rolls it back
+    drop(tx);
+
+    reader.rollback().await.unwrap();
+
+    let mut tx = conn.begin(Default::default()).await.unwrap();
+
+    tx.commit().await.unwrap();
+
+    assert_eq!(select_x(&mut db).await, Vec::<i64>::new());
+
+    // and the connection is usable, a retried commit succeeds
+    let mut reader = other.begin().await.unwrap();
+
+    assert_eq!(select_x_tx(&mut reader).await, Vec::<i64>::new());
+
+    let mut tx = db.begin().await.unwrap();
+
+    tx.prepare("INSERT INTO t VALUES(2);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    assert_eq!(tx.commit().await.unwrap_err().kind(), ErrorKind::Busy);
+
+    reader.rollback().await.unwrap();
+
+    tx.commit().await.unwrap();
+
+    drop(tx);
+
+    assert_eq!(select_x(&mut db).await, vec![2]);
+}
+
+async fn select_x_tx(tx: &mut Transaction) -> Vec<i64> {
+    let mut stmt = tx.prepare("SELECT x FROM t ORDER BY x").await.unwrap();
+
+    stmt.query(())
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.try_get(0).unwrap())
+        .collect()
+}
+
+#[async_std::test]
+async fn test_tx_closure() {
+    use futures::FutureExt;
+
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let uri = test_db_file("tx_closure");
+
+    let mut db = open("sqlite3", &uri).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let id = db
+        .transaction(|tx| {
+            async move {
+                let mut stmt = tx.prepare("INSERT INTO t VALUES(?);").await?;
+
+                Ok(stmt.execute(params![1]).await?.last_insert_id)
+            }
+            .boxed()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(id, 1);
+
+    let mut attempts = 0;
+
+    let err = db
+        .transaction(|tx| {
+            attempts += 1;
+
+            async move {
+                let mut stmt = tx.prepare("INSERT INTO t VALUES(?);").await?;
+
+                stmt.execute(params![2]).await?;
+
+                // the constraint error is not retried, and rolls back the insert of 2
+                stmt.execute(params![1]).await?;
+
+                Ok(())
+            }
+            .boxed()
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Constraint);
+    assert_eq!(attempts, 1);
+
+    let panicked = std::panic::AssertUnwindSafe(db.transaction::<_, ()>(|tx| {
+        async move {
+            tx.prepare("INSERT INTO t VALUES(3);")
+                .await?
+                .execute(())
+                .await?;
+
+            panic!("rollback on panic");
+        }
+        .boxed()
+    }))
+    .catch_unwind()
+    .await;
+
+    assert!(panicked.is_err());
+
+    assert_eq!(select_x(&mut db).await, vec![1]);
+
+    // holds the write lock for a while
+    let mut other = open("sqlite3", &uri).unwrap();
+
+    let mut locked = other
+        .begin_with(TransactionOptions {
+            locking_mode: LockingMode::Immediate,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let err = db
+        .transaction_with(Default::default(), RetryPolicy::never(), |tx| {
+            async move {
+                tx.prepare("INSERT INTO t VALUES(4);")
+                    .await?
+                    .execute(())
+                    .await?;
+
+                Ok(())
+            }
+            .boxed()
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Busy);
+
+    let unlock = async_std::task::spawn(async move {
+        async_std::task::sleep(std::time::Duration::from_millis(100)).await;
+
+        locked.commit().await.unwrap();
+    });
+
+    let mut attempts = 0;
+
+    let policy = RetryPolicy {
+        max_retries: 100,
+        initial_backoff: std::time::Duration::from_millis(5),
+        max_backoff: std::time::Duration::from_millis(20),
+    };
+
+    db.transaction_with(Default::default(), policy, |tx| {
+        attempts += 1;
+
+        async move {
+            tx.prepare("INSERT INTO t VALUES(4);")
+                .await?
+                .execute(())
+                .await?;
+
+            Ok(())
+        }
+        .boxed()
+    })
+    .await
+    .unwrap();
+
+    unlock.await;
+
+    assert!(attempts > 1);
+
+    assert_eq!(select_x(&mut db).await, vec![1, 4]);
+}
+
+/// Rows keep the finalized statement and the closed connection alive
+async fn rows_outlive_statement(mut driver: impl driver::Driver, name: &str) {
+    let mut conn = driver
+        .open(&format!("file:{}?mode=memory&cache=shared", name))
+        .await
+        .unwrap();
+
+    conn.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    conn.prepare("INSERT INTO t VALUES(1), (2);")
+        .await
+        .unwrap()
+        .execute(vec![])
+        .await
+        .unwrap();
+
+    let mut stmt = conn.prepare("SELECT x FROM t ORDER BY x").await.unwrap();
+
+    let mut rows = stmt.query(vec![]).await.unwrap();
+
+    drop(stmt);
+    drop(conn);
+
+    for x in [1, 2] {
+        assert!(rows.next().await.unwrap());
+
+        assert_eq!(
+            rows.get(Placeholder::Index(0), ColumnType::I64)
+                .await
+                .unwrap(),
+            Value::I64(x)
+        );
+    }
+
+    assert!(!rows.next().await.unwrap());
+}
+
+#[async_std::test]
+async fn test_rows_outlive_statement() {
+    _ = pretty_env_logger::try_init();
+
+    rows_outlive_statement(sync_driver::SyncDriver::default(), "sync_rows_outlive").await;
+
+    #[cfg(feature = "async-sqlite3")]
+    rows_outlive_statement(async_driver::AsyncDriver::new(), "async_rows_outlive").await;
+}
+
+#[async_std::test]
+async fn test_tx_owns_connection() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open_with(
+        "sqlite3",
+        &test_db_file("tx_owns_connection"),
+        PoolOptions {
+            max_open_connections: 1,
+            acquire_timeout: Some(std::time::Duration::from_millis(100)),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut tx = db.begin().await.unwrap();
+
+    {
+        let mut insert = tx.prepare("INSERT INTO t VALUES(?);").await.unwrap();
+        let mut select = tx.prepare("SELECT x FROM t ORDER BY x").await.unwrap();
+
+        insert.execute(params![1]).await.unwrap();
+
+        let mut rows = select.query(()).await.unwrap();
+
+        assert!(rows.next().await.unwrap());
+
+        // the transaction connection is not returned to the pool
+        assert_eq!(db.pool_status().idle_connections, 0);
+
+        assert_eq!(
+            db.prepare("SELECT 1").await.map(|_| ()).unwrap_err().kind(),
+            ErrorKind::Timeout
+        );
+
+        insert.execute(params![2]).await.unwrap();
+    }
+
+    tx.commit().await.unwrap();
+
+    drop(tx);
+
+    assert_eq!(db.pool_status().idle_connections, 1);
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2]);
+}
+
+/// Database for the multi-threaded executor tests, in WAL mode so readers are not blocked by writers
+async fn send_test_db(name: &str) -> Database {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file(name)).unwrap();
+
+    db.prepare("PRAGMA journal_mode = WAL;")
+        .await
+        .unwrap()
+        .query(())
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    db
+}
+
+/// Insert `x` and returns the number of rows `<= x`, run on the spawned tasks
+async fn send_workload(mut db: Database, x: i64) -> rdbc::Result<i64> {
+    use futures::FutureExt;
+
+    let options = TransactionOptions {
+        locking_mode: LockingMode::Immediate,
+        ..Default::default()
+    };
+
+    let policy = RetryPolicy {
+        max_retries: 1000,
+        initial_backoff: std::time::Duration::from_millis(1),
+        max_backoff: std::time::Duration::from_millis(10),
+    };
+
+    db.transaction_with(options, policy, move |tx| {
+        async move {
+            tx.prepare("INSERT INTO t VALUES(?);")
+                .await?
+                .execute(params![x])
+                .await?;
+
+            Ok(())
+        }
+        .boxed()
+    })
+    .await?;
+
+    let mut stmt = db.prepare("SELECT count(*) FROM t WHERE x <= ?").await?;
+
+    let mut rows = stmt.query(params![x]).await?;
+
+    rows.next().await?;
+
+    i64::from_value(&rows.get(0, ColumnType::I64).await?)
+}
+
+#[async_std::test]
+async fn test_send_async_std() {
+    let mut db = send_test_db("send_async_std").await;
+
+    let tasks = (1..=16)
+        .map(|x| async_std::task::spawn(send_workload(db.clone(), x)))
+        .collect::<Vec<_>>();
+
+    for task in tasks {
+        assert!(task.await.unwrap() >= 1);
+    }
+
+    assert_eq!(select_x(&mut db).await, (1..=16).collect::<Vec<_>>());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_send_tokio() {
+    let mut db = send_test_db("send_tokio").await;
+
+    let tasks = (1..=16)
+        .map(|x| tokio::spawn(send_workload(db.clone(), x)))
+        .collect::<Vec<_>>();
+
+    for task in tasks {
+        assert!(task.await.unwrap().unwrap() >= 1);
+    }
+
+    assert_eq!(select_x(&mut db).await, (1..=16).collect::<Vec<_>>());
+}
+
+/// Counts forever
+const ENDLESS_QUERY: &str =
+    "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c";
+
+#[async_std::test]
+async fn test_query_timeout() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let timeout = std::time::Duration::from_millis(100);
+
+    let mut db = open("sqlite3", &test_db_file("query_timeout")).unwrap();
+
+    let mut stmt = db.prepare(ENDLESS_QUERY).await.unwrap();
+
+    stmt.set_timeout(Some(timeout));
+
+    let started = std::time::Instant::now();
+
+    let err = stmt.query(()).await.unwrap().next().await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+    assert!(started.elapsed() >= timeout);
+
+    db.set_query_timeout(Some(timeout));
+
+    let mut stmt = db.prepare(ENDLESS_QUERY).await.unwrap();
+
+    let err = stmt.query(()).await.unwrap().next().await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+
+    let tx = db.begin().await.unwrap();
+
+    let mut stmt = tx.prepare(ENDLESS_QUERY).await.unwrap();
+
+    let err = stmt.query(()).await.unwrap().next().await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+
+    let mut stmt = tx
+        .prepare("WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 10) SELECT count(*) FROM c")
+        .await
+        .unwrap();
+
+    // the statement timeout overrides the database one
+    stmt.set_timeout(None);
+
+    let count: i64 = stmt
+        .query(())
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap()
+        .try_get(0)
+        .unwrap();
+
+    assert_eq!(count, 10);
+}
+
+#[async_std::test]
+async fn test_query_cancel() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("query_cancel")).unwrap();
+
+    let mut stmt = db.prepare(ENDLESS_QUERY).await.unwrap();
+
+    let cancel = stmt.cancel_handle();
+
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    let canceller = {
+        let done = done.clone();
+
+        // retry in case the query is not running yet
+        std::thread::spawn(move || {
+            while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+
+                futures::executor::block_on(cancel.cancel()).unwrap();
+            }
+        })
+    };
+
+    let err = stmt.query(()).await.unwrap().next().await.unwrap_err();
+
+    done.store(true, std::sync::atomic::Ordering::SeqCst);
+
+    canceller.join().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Cancelled);
+
+    // cancelling without a running query does nothing
+    stmt.cancel_handle().cancel().await.unwrap();
+
+    let mut stmt = db.prepare("SELECT 1").await.unwrap();
+
+    let one: i64 = stmt
+        .query(())
+        .await
+        .unwrap()
+        .fetch_one::<Row>()
+        .await
+        .unwrap()
+        .try_get(0)
+        .unwrap();
+
+    assert_eq!(one, 1);
+
+    drop(stmt);
+
+    // the handle of another statement of the same connection doesn't interrupt it
+    let tx = db.begin().await.unwrap();
+
+    let idle = tx.prepare("SELECT 2").await.unwrap();
+
+    let mut slow = tx.prepare(ENDLESS_QUERY).await.unwrap();
+
+    slow.set_timeout(Some(std::time::Duration::from_millis(300)));
+
+    let cancel = idle.cancel_handle();
+
+    done.store(false, std::sync::atomic::Ordering::SeqCst);
+
+    let canceller = {
+        let done = done.clone();
+
+        std::thread::spawn(move || {
+            while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+
+                futures::executor::block_on(cancel.cancel()).unwrap();
+            }
+        })
+    };
+
+    let err = slow.query(()).await.unwrap().next().await.unwrap_err();
+
+    done.store(true, std::sync::atomic::Ordering::SeqCst);
+
+    canceller.join().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+}
+
+#[async_std::test]
+async fn test_statement_cache() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open_with(
+        "sqlite3",
+        &test_db_file("statement_cache"),
+        PoolOptions {
+            max_open_connections: 1,
+            statement_cache_capacity: 2,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let status = db.statement_cache_status();
+
+    for x in 1..=3 {
+        db.prepare("INSERT INTO t VALUES(?);")
+            .await
+            .unwrap()
+            .execute(params![x])
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        db.statement_cache_status(),
+        StatementCacheStatus {
+            hits: status.hits + 2,
+            misses: status.misses + 1,
+        }
+    );
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 3]);
+
+    let status = db.statement_cache_status();
+
+    // the transaction shares the connection statement cache
+    let mut tx = db.begin().await.unwrap();
+
+    {
+        // the same query prepared twice at the same time gets two statements
+        let mut insert = tx.prepare("INSERT INTO t VALUES(?);").await.unwrap();
+        let mut again = tx.prepare("INSERT INTO t VALUES(?);").await.unwrap();
+
+        insert.execute(params![4]).await.unwrap();
+        again.execute(params![5]).await.unwrap();
+    }
+
+    assert_eq!(
+        db.statement_cache_status(),
+        StatementCacheStatus {
+            hits: status.hits + 1,
+            misses: status.misses + 1,
+        }
+    );
+
+    assert_eq!(select_x_tx(&mut tx).await, vec![1, 2, 3, 4, 5]);
+
+    tx.commit().await.unwrap();
+
+    drop(tx);
+
+    // a rollback keeps the cache if the transaction didn't change the schema
+    let mut tx = db.begin().await.unwrap();
+
+    tx.prepare("INSERT INTO t VALUES(?);")
+        .await
+        .unwrap()
+        .execute(params![10])
+        .await
+        .unwrap();
+
+    tx.rollback().await.unwrap();
+
+    drop(tx);
+
+    let status = db.statement_cache_status();
+
+    db.prepare("INSERT INTO t VALUES(?);").await.unwrap();
+
+    assert_eq!(db.statement_cache_status().hits, status.hits + 1);
+
+    // the statements of a rolled back table are not reused
+    let mut tx = db.begin().await.unwrap();
+
+    tx.execute_script("CREATE TABLE u(x INTEGER);")
+        .await
+        .unwrap();
+
+    tx.prepare("SELECT x FROM u").await.unwrap();
+
+    tx.rollback().await.unwrap();
+
+    drop(tx);
+
+    assert!(db.prepare("SELECT x FROM u").await.is_err());
+
+    // the driver reports the schema changes, a keyword in a query is not one
+    let mut tx = db.begin().await.unwrap();
+
+    tx.prepare("SELECT 'drop' FROM t").await.unwrap();
+
+    tx.rollback().await.unwrap();
+
+    drop(tx);
+
+    let status = db.statement_cache_status();
+
+    db.prepare("SELECT 'drop' FROM t").await.unwrap();
+
+    assert_eq!(db.statement_cache_status().hits, status.hits + 1);
+
+    // nor is it needed to find the DDL of a script run within a savepoint
+    let mut tx = db.begin().await.unwrap();
+
+    let mut nested = tx.begin_nested().await.unwrap();
+
+    nested
+        .execute_script("SELECT 1; ALTER TABLE t ADD COLUMN y TEXT;")
+        .await
+        .unwrap();
+
+    nested.prepare("SELECT y FROM t").await.unwrap();
+
+    nested.rollback().await.unwrap();
+
+    drop(nested);
+
+    assert!(tx.prepare("SELECT y FROM t").await.is_err());
+
+    tx.rollback().await.unwrap();
+
+    drop(tx);
+
+    // capacity 2, the INSERT statement is evicted by the least recently used ones
+    for query in ["SELECT 1", "SELECT 2"] {
+        db.prepare(query).await.unwrap();
+    }
+
+    let status = db.statement_cache_status();
+
+    db.prepare("INSERT INTO t VALUES(?);")
+        .await
+        .unwrap()
+        .execute(params![6])
+        .await
+        .unwrap();
+
+    assert_eq!(db.statement_cache_status().misses, status.misses + 1);
+
+    // capacity 0 disables the cache
+    let mut db = open_with(
+        "sqlite3",
+        &test_db_file("statement_cache_disabled"),
+        PoolOptions {
+            statement_cache_capacity: 0,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    for _ in 0..2 {
+        db.prepare("SELECT 1").await.unwrap();
+    }
+
+    assert_eq!(
+        db.statement_cache_status(),
+        StatementCacheStatus { hits: 0, misses: 0 }
+    );
+}
+
+#[async_std::test]
+async fn test_execute_batch() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("execute_batch")).unwrap();
+
+    db.prepare("CREATE TABLE t(x INTEGER PRIMARY KEY, y TEXT NOT NULL);")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let mut stmt = db.prepare("INSERT INTO t(y) VALUES(?);").await.unwrap();
+
+    let results = stmt
+        .execute_batch((1..=3).map(|i| [format!("row {}", i)]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        results,
+        (1..=3)
+            .map(|i| ExecuteResult {
+                last_insert_id: i,
+                raws_affected: 1,
+            })
+            .collect::<Vec<_>>()
+    );
+
+    assert!(stmt
+        .execute_batch(Vec::<Vec<Arg>>::new())
+        .await
+        .unwrap()
+        .is_empty());
+
+    // stops at the failed execution, the previous ones are not undone
+    let err = stmt
+        .execute_batch([
+            [Value::String("row 4".to_owned())],
+            [Value::Null],
+            [Value::String("row 5".to_owned())],
+        ])
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Constraint);
+    assert!(err.to_string().contains("index 1"), "{}", err);
+    assert!(err.downcast_ref::<error::Sqlite3Error>().is_some());
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 3, 4]);
+
+    let result = stmt
+        .execute_stream(futures::stream::iter(
+            (0..EXECUTE_STREAM_BATCH_SIZE * 2 + 10).map(|i| [i.to_string()]),
+        ))
+        .await
+        .unwrap();
+
+    let inserted = EXECUTE_STREAM_BATCH_SIZE as u64 * 2 + 10;
+
+    assert_eq!(
+        result,
+        ExecuteResult {
+            last_insert_id: 4 + inserted,
+            raws_affected: inserted,
+        }
+    );
+
+    // the failed row index is counted from the stream start
+    let err = stmt
+        .execute_stream(futures::stream::iter(
+            (0..EXECUTE_STREAM_BATCH_SIZE + 1).map(|i| match i {
+                i if i == EXECUTE_STREAM_BATCH_SIZE => [Value::Null],
+                i => [Value::String(i.to_string())],
+            }),
+        ))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Constraint);
+    assert!(
+        format!("{:?}", err).contains(&format!("after {} rows", EXECUTE_STREAM_BATCH_SIZE)),
+        "{:?}",
+        err
+    );
+}
+
+#[async_std::test]
+async fn test_execute_script() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("execute_script")).unwrap();
+
+    let result = db
+        .execute_script(
+            r#"
+            -- schema
+            CREATE TABLE t(x INTEGER PRIMARY KEY, y TEXT);
+            CREATE TABLE log(y TEXT);
+
+            /* a trigger body has its own statements; */
+            CREATE TRIGGER t_log AFTER INSERT ON t BEGIN
+                INSERT INTO log VALUES(new.y);
+            END;
+
+            INSERT INTO t(y) VALUES('a;b'), ('c');;
+            SELECT * FROM t; -- rows are ignored
+            "#,
+        )
+        .await
+        .unwrap();
+
+    // the trigger inserts are counted too
+    assert_eq!(result.raws_affected, 4);
+    assert_eq!(select_x(&mut db).await, vec![1, 2]);
+
+    // prepare doesn't silently drop the statements after the first one
+    assert_eq!(
+        db.prepare("INSERT INTO t(y) VALUES('d'); INSERT INTO t(y) VALUES('e');")
+            .await
+            .map(|_| ())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::Syntax
+    );
+
+    db.prepare("INSERT INTO t(y) VALUES('d'); -- comment")
+        .await
+        .unwrap()
+        .execute(())
+        .await
+        .unwrap();
+
+    let script = "INSERT INTO t VALUES(10, 'e');\nINSERT INTO t VALUES(1, 'dup');\nINSERT INTO t VALUES(11, 'f');";
+
+    let err = db.execute_script(script).await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Constraint);
+    assert_eq!(
+        err.downcast_ref::<driver::ScriptPosition>(),
+        Some(&driver::ScriptPosition {
+            index: 1,
+            offset: script.find("INSERT INTO t VALUES(1,").unwrap(),
+            line: 2,
+        })
+    );
+
+    // the statements before the failed one are not undone
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 3, 10]);
+
+    let err = db
+        .execute_script_in_transaction(
+            "INSERT INTO t VALUES(20, 'g');\nINSERT INTO t VALUES(1, 'dup');",
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Constraint);
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 3, 10]);
+
+    let err = db.execute_script("SELECT 1;\nSELEC 2;").await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Syntax);
+    assert_eq!(
+        err.downcast_ref::<driver::ScriptPosition>().map(|p| p.line),
+        Some(2)
+    );
+
+    let mut tx = db.begin().await.unwrap();
+
+    tx.execute_script("INSERT INTO t VALUES(20, 'g'); INSERT INTO t VALUES(21, 'h');")
+        .await
+        .unwrap();
+
+    tx.commit().await.unwrap();
+
+    drop(tx);
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 3, 10, 20, 21]);
+}
+
+#[async_std::test]
+async fn test_execute_returning() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("execute_returning")).unwrap();
+
+    db.execute_script(
+        "CREATE TABLE t(x INTEGER PRIMARY KEY, y TEXT, created TEXT NOT NULL DEFAULT 'now');",
+    )
+    .await
+    .unwrap();
+
+    let mut stmt = db
+        .prepare("INSERT INTO t(y) VALUES(?), (?), (?) RETURNING x, created")
+        .await
+        .unwrap();
+
+    let (result, rows) = stmt
+        .execute_returning(params!["a", "b", "c"])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result,
+        ExecuteResult {
+            last_insert_id: 3,
+            raws_affected: 3,
+        }
+    );
+
+    let rows: Vec<(i64, String)> = rows
+        .fetch_all::<Row>()
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| (row.try_get(0).unwrap(), row.try_get(1).unwrap()))
+        .collect();
+
+    assert_eq!(
+        rows,
+        vec![
+            (1, "now".to_owned()),
+            (2, "now".to_owned()),
+            (3, "now".to_owned())
+        ]
+    );
+
+    // all the changes are made even if the returned rows are not fetched
+    let (result, mut rows) = stmt
+        .execute_returning(params!["d", "e", "f"])
+        .await
+        .unwrap();
+
+    assert_eq!(result.raws_affected, 3);
+    assert!(rows.next().await.unwrap());
+
+    drop(rows);
+
+    assert_eq!(select_x(&mut db).await, vec![1, 2, 3, 4, 5, 6]);
+
+    let mut stmt = db
+        .prepare("UPDATE t SET y = 'z' WHERE x > ? RETURNING x")
+        .await
+        .unwrap();
+
+    let (result, rows) = stmt.execute_returning(params![10]).await.unwrap();
+
+    assert_eq!(result.raws_affected, 0);
+    assert!(rows.fetch_optional::<Row>().await.unwrap().is_none());
+
+    let (result, rows) = stmt.execute_returning(params![4]).await.unwrap();
+
+    assert_eq!(result.raws_affected, 2);
+    assert_eq!(returned_x(rows).await, vec![5, 6]);
+
+    let mut stmt = db
+        .prepare("INSERT INTO t(x) VALUES(?) RETURNING x")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        stmt.execute_returning(params![1])
+            .await
+            .map(|_| ())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::Constraint
+    );
+
+    // the statement is usable after a failure
+    let (_, rows) = stmt.execute_returning(params![7]).await.unwrap();
+
+    assert_eq!(returned_x(rows).await, vec![7]);
+
+    // out of range indexes of the buffered rows fail, also above i32::MAX
+    let (_, mut rows) = stmt.execute_returning(params![8]).await.unwrap();
+
+    assert!(rows.next().await.unwrap());
+
+    for index in [1, i32::MAX as u64 + 1, u64::MAX] {
+        let err = rows.get(index, ColumnType::I64).await.unwrap_err();
+
+        assert!(
+            matches!(
+                err.downcast_ref::<error::Sqlite3Error>(),
+                Some(error::Sqlite3Error::OutOfRange(i)) if *i == index
+            ),
+            "{}",
+            err
+        );
+    }
+
+    assert_eq!(rows.get(0, ColumnType::I64).await.unwrap(), Value::I64(8));
+
+    drop(rows);
+
+    let err = stmt
+        .execute_returning(vec![rdbc::Arg {
+            pos: Placeholder::Index(u64::MAX),
+            value: Value::I64(9),
+        }])
+        .await
+        .map(|_| ())
+        .unwrap_err();
+
+    assert!(
+        matches!(
+            err.downcast_ref::<error::Sqlite3Error>(),
+            Some(error::Sqlite3Error::OutOfRange(u64::MAX))
+        ),
+        "{}",
+        err
+    );
+}
+
+async fn returned_x(rows: Rows<'_>) -> Vec<i64> {
+    rows.fetch_all::<Row>()
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.try_get(0).unwrap())
+        .collect()
+}
+
+#[async_std::test]
+async fn test_rows_get_row() {
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let mut db = open("sqlite3", &test_db_file("rows_get_row")).unwrap();
+
+    let rows = ROWS_PREFETCH * 2 + 10;
+
+    db.execute_script(&format!(
+        "CREATE TABLE t(x INTEGER PRIMARY KEY, b BOOL);
+        WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < {})
+        INSERT INTO t SELECT x, x % 2 FROM c;",
+        rows
+    ))
+    .await
+    .unwrap();
+
+    let query = "SELECT x, b, x * 0.5 AS f, 'row ' || x AS s, NULL AS n FROM t ORDER BY x";
+
+    let mut stmt = db.prepare(query).await.unwrap();
+
+    let streamed = stmt
+        .query(())
+        .await
+        .unwrap()
+        .fetch_all::<Row>()
+        .await
+        .unwrap();
+
+    assert_eq!(streamed.len(), rows);
+
+    // values are fetched with the column types
+    assert_eq!(
+        streamed[2].values(),
+        &[
+            Value::I64(3),
+            Value::Bool(true),
+            Value::F64(1.5),
+            Value::String("row 3".to_owned()),
+            Value::Null
+        ]
+    );
+
+    let mut fetched = vec![];
+
+    let mut rows = stmt.query(()).await.unwrap();
+
+    while rows.next().await.unwrap() {
+        fetched.push(rows.get_row().await.unwrap());
+    }
+
+    drop(rows);
+
+    assert_eq!(fetched, streamed);
+
+    for limit in [0, 1, ROWS_PREFETCH - 1, ROWS_PREFETCH, ROWS_PREFETCH + 1] {
+        let mut stmt = db
+            .prepare(&format!("{} LIMIT {}", query, limit))
+            .await
+            .unwrap();
+
+        let rows = stmt
+            .query(())
+            .await
+            .unwrap()
+            .fetch_all::<Row>()
+            .await
+            .unwrap();
+
+        assert_eq!(rows, streamed[..limit]);
+    }
+}
+
+#[cfg(feature = "async-sqlite3")]
+#[async_std::test]
+async fn test_threaded_driver() {
+    use rdbc::driver::Driver;
+
+    _ = pretty_env_logger::try_init();
+
+    let mut driver = async_driver::AsyncDriver::new();
+
+    assert!(driver
+        .open("file:.test/missing/threaded.db?mode=ro")
+        .await
+        .is_err());
+
+    let mut first = driver.open(":memory:").await.unwrap();
+    let second = driver.open(":memory:").await.unwrap();
+
+    // Connections report the blocking connection id, not the worker registry key
+    assert!(first.id().starts_with("0x"));
+    assert_ne!(first.id(), second.id());
+
+    drop(second);
+
+    let mut stmt = first.prepare("SELECT 1, 'a'").await.unwrap();
+
+    let mut rows = stmt.query(vec![]).await.unwrap();
+
+    assert!(rows.next().await.unwrap());
+
+    assert_eq!(
+        rows.get_row().await.unwrap(),
+        vec![Value::I64(1), Value::String("a".to_owned())]
+    );
+
+    assert!(!rows.next().await.unwrap());
+}
+
+#[cfg(feature = "async-sqlite3")]
+#[async_std::test]
+async fn test_async_driver_workers() {
+    use rdbc::driver::Driver;
+
+    _ = pretty_env_logger::try_init();
+
+    let url = test_db_file("async_driver_workers");
+
+    let mut driver = async_driver::AsyncDriver::with_workers(2);
+
+    // connections are assigned to the workers in turn
+    let mut slow_conn = driver.open(&url).await.unwrap();
+    let mut fast_conn = driver.open(&url).await.unwrap();
+
+    let mut slow = slow_conn.prepare(ENDLESS_QUERY).await.unwrap();
+
+    let mut slow_rows = slow.query(vec![]).await.unwrap();
+
+    // keeps the worker of slow_conn busy until cancelled
+    let slow_next = slow_rows.next();
+
+    let fast = async {
+        let mut stmt = fast_conn.prepare("SELECT 1").await.unwrap();
+
+        let mut rows = stmt.query(vec![]).await.unwrap();
+
+        assert!(rows.next().await.unwrap());
+
+        rows.get(Placeholder::Index(0), ColumnType::I64)
+            .await
+            .unwrap()
+    };
+
+    let one = async_std::future::timeout(std::time::Duration::from_secs(10), fast)
+        .await
+        .expect("query blocked by a slow query on another connection");
+
+    assert_eq!(one, Value::I64(1));
+
+    let cancel = slow.cancel_handle();
+
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    let canceller = {
+        let done = done.clone();
+
+        // retry in case the query is not running yet
+        std::thread::spawn(move || {
+            while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                futures::executor::block_on(cancel.cancel()).unwrap();
+
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+        })
+    };
+
+    let err = slow_next.await.unwrap_err();
+
+    done.store(true, std::sync::atomic::Ordering::SeqCst);
+
+    canceller.join().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Cancelled);
+}
+
+#[cfg(feature = "async-sqlite3")]
+#[async_std::test]
+async fn test_async_driver_shutdown() {
+    use rdbc::driver::Driver;
+
+    _ = pretty_env_logger::try_init();
+
+    let url = test_db_file("async_driver_shutdown");
+
+    let mut driver = async_driver::AsyncDriver::with_workers(2);
+
+    let mut conn = driver.open(&url).await.unwrap();
+
+    conn.execute_script("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap();
+
+    let mut tx = conn.begin(Default::default()).await.unwrap();
+
+    let mut stmt = tx.prepare("INSERT INTO t VALUES(1);").await.unwrap();
+
+    // queued before the shutdown, so it still runs
+    let pending = stmt.execute(vec![]);
+
+    driver.shutdown().unwrap();
+
+    assert_eq!(pending.await.unwrap().raws_affected, 1);
+
+    let is_shut_down = |err: rdbc::Error| {
+        err.kind() == ErrorKind::Connection && err.to_string().contains("driver shut down")
+    };
+
+    assert!(is_shut_down(stmt.execute(vec![]).await.unwrap_err()));
+    assert!(is_shut_down(tx.commit().await.unwrap_err()));
+    assert!(is_shut_down(conn.prepare("SELECT 1").await.err().unwrap()));
+    assert!(is_shut_down(driver.open(&url).await.err().unwrap()));
+
+    // shutting down again does nothing
+    driver.shutdown().unwrap();
+
+    // the uncommitted transaction was rolled back and the connection closed
+    let mut conn = sync_driver::SyncDriver::default().open(&url).await.unwrap();
+
+    let mut stmt = conn.prepare("SELECT count(*) FROM t").await.unwrap();
+
+    let mut rows = stmt.query(vec![]).await.unwrap();
+
+    assert!(rows.next().await.unwrap());
+
+    assert_eq!(
+        rows.get(Placeholder::Index(0), ColumnType::I64)
+            .await
+            .unwrap(),
+        Value::I64(0)
+    );
+}
+
+#[cfg(feature = "async-sqlite3")]
+#[async_std::test]
+async fn test_unregister_driver_shuts_down() {
+    _ = pretty_env_logger::try_init();
+
+    let mut datasource = DataSource::new();
+
+    datasource
+        .register_driver("sqlite3", async_driver::AsyncDriver::new())
+        .unwrap();
+
+    let mut db = datasource.open("sqlite3", ":memory:").unwrap();
+
+    db.execute_script("SELECT 1").await.unwrap();
+
+    datasource.unregister_driver("sqlite3").unwrap();
+
+    assert!(db.execute_script("SELECT 1").await.is_err());
+}
+
+#[cfg(feature = "async-sqlite3")]
+#[async_std::test]
+async fn test_threaded_driver_panic() {
+    use rdbc::driver::{threaded, Driver};
+
+    _ = pretty_env_logger::try_init();
+
+    struct PanicDriver {}
+
+    /// Panics running the script "panic"
+    struct PanicConnection(sqlite3_rs::Connection);
+
+    impl threaded::BlockingDriver for PanicDriver {
+        type Connection = PanicConnection;
+
+        fn open(&mut self, name: &str) -> rdbc::Result<Self::Connection> {
+            if name == "panic" {
+                panic!("open {}", name);
+            }
+
+            sqlite3_rs::Connection::open(name).map(PanicConnection)
+        }
+    }
+
+    impl threaded::BlockingConnection for PanicConnection {
+        type Statement = sqlite3_rs::Statement;
+        type Transaction = sqlite3_rs::Transaction;
+
+        fn id(&self) -> &str {
+            &self.0.id
+        }
+
+        fn prepare(&mut self, query: &str) -> rdbc::Result<Self::Statement> {
+            self.0.prepare(query)
+        }
+
+        fn execute_script(&mut self, script: &str) -> rdbc::Result<rdbc::ExecuteResult> {
+            if script == "panic" {
+                panic!("execute {}", script);
+            }
+
+            self.0.execute_script(script)
+        }
+
+        fn begin(&mut self, options: rdbc::TransactionOptions) -> rdbc::Result<Self::Transaction> {
+            self.0.begin(options)
+        }
+
+        fn ping(&mut self) -> rdbc::Result<()> {
+            self.0.ping()
+        }
+
+        fn reset(&mut self) -> rdbc::Result<bool> {
+            self.0.reset()
+        }
+    }
+
+    let mut driver = threaded::ThreadedDriver::new(PanicDriver {});
+
+    let mut conn = driver.open(":memory:").await.unwrap();
+
+    let err = driver.open("panic").await.err().unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Connection);
+    assert!(err.to_string().contains("panicked: open panic"));
+
+    // the worker keeps running, with the resources it had
+    conn.ping().await.unwrap();
+
+    let mut broken = driver.open(":memory:").await.unwrap();
+
+    let err = broken.execute_script("panic").await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Connection);
+    assert!(err.to_string().contains("panicked: execute panic"));
+
+    // only the connection that panicked is dropped
+    let err = broken.ping().await.unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Connection);
+    assert!(err.to_string().contains("panicked"));
+
+    conn.ping().await.unwrap();
+
+    driver.shutdown().unwrap();
+}
+
+#[cfg(feature = "async-sqlite3")]
+#[async_std::test]
+async fn test_async_driver_queue_capacity() {
+    use rdbc::driver::{threaded, Driver};
+
+    _ = pretty_env_logger::try_init();
+
+    let url = test_db_file("async_driver_queue_capacity");
+
+    let mut driver = async_driver::AsyncDriver::with_options(threaded::ThreadedOptions {
+        workers: 1,
+        queue_capacity: 2,
+    });
+
+    let monitor = driver.queue_monitor();
+
+    let mut slow_conn = driver.open(&url).await.unwrap();
+    let mut conn = driver.open(&url).await.unwrap();
+
+    conn.execute_script("CREATE TABLE t(x INTEGER PRIMARY KEY);")
+        .await
+        .unwrap();
+
+    let mut stmt = conn.prepare("INSERT INTO t VALUES(?);").await.unwrap();
+
+    let mut slow = slow_conn.prepare(ENDLESS_QUERY).await.unwrap();
+
+    let mut slow_rows = slow.query(vec![]).await.unwrap();
+
+    // keeps the only worker busy until cancelled
+    let slow_next = slow_rows.next();
+
+    // wait for the worker to take the slow task
+    while monitor.status().queued > 0 {
+        async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    // sending to the full queue doesn't block the caller
+    let mut inserts = (1..=6)
+        .map(|x| {
+            stmt.execute(vec![rdbc::Arg {
+                pos: Placeholder::Index(1),
+                value: Value::I64(x),
+            }])
+        })
+        .collect::<Vec<_>>();
+
+    // only the capacity is queued, the other senders stay pending without their task
+    for insert in &mut inserts {
+        assert!(futures::poll!(insert).is_pending());
+    }
+
+    // the task of a sender dropped while waiting still runs in its turn
+    drop(inserts.pop());
+
+    assert_eq!(
+        monitor.status(),
+        threaded::QueueStatus {
+            queued: 2,
+            waiting: 3,
+            capacity: 2,
+        }
+    );
+
+    let cancel = slow.cancel_handle();
+
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    let canceller = {
+        let done = done.clone();
+
+        // retry in case the query is not running yet
+        std::thread::spawn(move || {
+            while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                futures::executor::block_on(cancel.cancel()).unwrap();
+
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+        })
+    };
+
+    assert_eq!(slow_next.await.unwrap_err().kind(), ErrorKind::Cancelled);
+
+    done.store(true, std::sync::atomic::Ordering::SeqCst);
+
+    canceller.join().unwrap();
+
+    // the waiting tasks ran in send order
+    for (x, insert) in (1..=5).zip(inserts) {
+        assert_eq!(insert.await.unwrap().last_insert_id, x as u64);
+    }
+
+    let insert = stmt.execute(vec![rdbc::Arg {
+        pos: Placeholder::Index(1),
+        value: Value::Null,
+    }]);
+
+    assert_eq!(insert.await.unwrap().last_insert_id, 7);
+
+    assert_eq!(
+        driver.queue_status().queued + driver.queue_status().waiting,
+        0
+    );
+}
+
+#[async_std::test]
+async fn test_open_options() {
+    use rdbc::driver::Driver;
+
+    _ = pretty_env_logger::try_init();
+    _ = register_sqlite3();
+
+    let path = test_db_file("open_options");
+
+    let path = path.strip_prefix("file:").unwrap();
+
+    let url = format!("sqlite3://{}", path);
+
+    let mut db = open("sqlite3", &url).unwrap();
+
+    db.execute_script("CREATE TABLE t(x INTEGER PRIMARY KEY); INSERT INTO t VALUES(1);")
+        .await
+        .unwrap();
+
+    async fn count(db: &mut Database) -> Result<i64> {
+        let mut stmt = db.prepare("SELECT count(*) FROM t").await?;
+
+        let row = stmt.query(()).await?.fetch_one::<Row>().await?;
+
+        row.try_get(0)
+    }
+
+    async fn insert(db: &mut Database) -> Result<ExecuteResult> {
+        db.prepare("INSERT INTO t VALUES(NULL)")
+            .await?
+            .execute(())
+            .await
+    }
+
+    // read-only replica driver
+    _ = register_sqlite3_with(
+        "sqlite3-ro",
+        SqliteOptions {
+            read_only: true,
+            ..Default::default()
+        },
+    );
+
+    let mut replica = open("sqlite3-ro", &url).unwrap();
+
+    for _ in 0..2 {
+        assert_eq!(count(&mut replica).await.unwrap(), 1);
+    }
+
+    let err = insert(&mut replica).await.unwrap_err();
+
+    assert!(err.to_string().contains("readonly"), "{}", err);
+
+    // the url parameters restrict the driver options
+    let mut replica = open("sqlite3", &format!("{}?mode=ro", url)).unwrap();
+
+    assert_eq!(count(&mut replica).await.unwrap(), 1);
+    assert!(insert(&mut replica).await.is_err());
+
+    insert(&mut db).await.unwrap();
+
+    let mut dotfile = open("sqlite3", &format!("{}?vfs=unix-dotfile", url)).unwrap();
+
+    assert_eq!(count(&mut dotfile).await.unwrap(), 2);
+
+    // don't create missing databases
+    let mut driver = sync_driver::SyncDriver {
+        options: SqliteOptions {
+            create: false,
+            ..Default::default()
+        },
+    };
+
+    let err = driver
+        .open(&test_db_file("open_options_missing"))
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(err.kind(), ErrorKind::Connection);
+
+    assert!(driver.open(&url).await.is_ok());
+
+    let mut driver = sync_driver::SyncDriver {
+        options: SqliteOptions {
+            vfs: Some("no-such-vfs".to_owned()),
+            ..Default::default()
+        },
+    };
+
+    assert!(driver.open(&url).await.is_err());
+}
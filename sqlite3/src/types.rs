@@ -0,0 +1,179 @@
+//! Sqlite3 storage encodings of the [`driver::Value`] types without a native storage class
+//!
+//! - `Bool` is stored as INTEGER `0` or `1`
+//! - date and time values are stored as TEXT, e.g. `2022-07-31 12:30:59.123`,
+//!   and can also be loaded from unix time INTEGER or julian day REAL
+//! - `Decimal`, `Uuid` and `Json` are stored as TEXT
+use rdbc::Result;
+
+use rdbc::driver;
+
+use super::error::Sqlite3Error;
+
+#[cfg(feature = "chrono")]
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[cfg(feature = "chrono")]
+const TIME_FORMAT: &str = "%H:%M:%S%.f";
+
+#[cfg(feature = "chrono")]
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Julian day number of the unix epoch
+#[cfg(feature = "chrono")]
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+
+/// Returns the [`driver::ColumnType`] of a known declared type
+pub fn decltype_column_type(decltype: &str) -> Option<driver::ColumnType> {
+    let column_type = match decltype.to_uppercase().as_str() {
+        "BOOL" | "BOOLEAN" => driver::ColumnType::Bool,
+        #[cfg(feature = "chrono")]
+        "DATE" => driver::ColumnType::Date,
+        #[cfg(feature = "chrono")]
+        "TIME" => driver::ColumnType::Time,
+        #[cfg(feature = "chrono")]
+        "DATETIME" => driver::ColumnType::DateTime,
+        #[cfg(feature = "chrono")]
+        "TIMESTAMP" => driver::ColumnType::Timestamp,
+        #[cfg(feature = "decimal")]
+        decimal if decimal.starts_with("DECIMAL") => driver::ColumnType::Decimal,
+        #[cfg(feature = "uuid")]
+        "UUID" => driver::ColumnType::Uuid,
+        #[cfg(feature = "json")]
+        "JSON" => driver::ColumnType::Json,
+        _ => return None,
+    };
+
+    Some(column_type)
+}
+
+/// Encode `value` as one of the sqlite3 storage classes
+pub fn encode(value: driver::Value) -> Result<driver::Value> {
+    let value = match value {
+        driver::Value::Bool(v) => driver::Value::I64(v as i64),
+        #[cfg(feature = "chrono")]
+        driver::Value::Date(v) => driver::Value::String(v.format(DATE_FORMAT).to_string()),
+        #[cfg(feature = "chrono")]
+        driver::Value::Time(v) => driver::Value::String(v.format(TIME_FORMAT).to_string()),
+        #[cfg(feature = "chrono")]
+        driver::Value::DateTime(v) => driver::Value::String(v.format(DATETIME_FORMAT).to_string()),
+        #[cfg(feature = "chrono")]
+        driver::Value::Timestamp(v) => {
+            driver::Value::String(v.naive_utc().format(DATETIME_FORMAT).to_string())
+        }
+        #[cfg(feature = "decimal")]
+        driver::Value::Decimal(v) => driver::Value::String(v.to_string()),
+        #[cfg(feature = "uuid")]
+        driver::Value::Uuid(v) => driver::Value::String(v.to_string()),
+        #[cfg(feature = "json")]
+        driver::Value::Json(v) => driver::Value::String(v.to_string()),
+        value @ (driver::Value::I64(_)
+        | driver::Value::F64(_)
+        | driver::Value::String(_)
+        | driver::Value::Bytes(_)
+        | driver::Value::Null) => value,
+        // rdbc features not enabled for this crate
+        #[allow(unreachable_patterns)]
+        value => return Err(Sqlite3Error::UnsupportedType(format!("{:?}", value)).into()),
+    };
+
+    Ok(value)
+}
+
+/// Decode `value` loaded with its storage class as `column_type`
+pub fn decode(column_type: driver::ColumnType, value: driver::Value) -> Result<driver::Value> {
+    let decoded = match (column_type, &value) {
+        (_, driver::Value::Null) => Some(driver::Value::Null),
+        (driver::ColumnType::Bool, driver::Value::I64(v)) => Some(driver::Value::Bool(*v != 0)),
+        (driver::ColumnType::Bool, driver::Value::F64(v)) => Some(driver::Value::Bool(*v != 0.0)),
+        (driver::ColumnType::Bool, driver::Value::String(v)) => match v.to_lowercase().as_str() {
+            "true" | "1" => Some(driver::Value::Bool(true)),
+            "false" | "0" => Some(driver::Value::Bool(false)),
+            _ => None,
+        },
+        #[cfg(feature = "chrono")]
+        (driver::ColumnType::Date, v) => decode_datetime(v).map(|v| driver::Value::Date(v.date())),
+        #[cfg(feature = "chrono")]
+        (driver::ColumnType::Time, driver::Value::String(v)) => {
+            chrono::NaiveTime::parse_from_str(v, TIME_FORMAT)
+                .or_else(|_| chrono::NaiveTime::parse_from_str(v, "%H:%M"))
+                .ok()
+                .map(driver::Value::Time)
+        }
+        #[cfg(feature = "chrono")]
+        (driver::ColumnType::DateTime, v) => decode_datetime(v).map(driver::Value::DateTime),
+        #[cfg(feature = "chrono")]
+        (driver::ColumnType::Timestamp, v) => {
+            decode_datetime(v).map(|v| driver::Value::Timestamp(v.and_utc()))
+        }
+        #[cfg(feature = "decimal")]
+        (driver::ColumnType::Decimal, driver::Value::I64(v)) => {
+            Some(driver::Value::Decimal((*v).into()))
+        }
+        #[cfg(feature = "decimal")]
+        (driver::ColumnType::Decimal, driver::Value::F64(v)) => rust_decimal::Decimal::try_from(*v)
+            .ok()
+            .map(driver::Value::Decimal),
+        #[cfg(feature = "decimal")]
+        (driver::ColumnType::Decimal, driver::Value::String(v)) => v
+            .parse::<rust_decimal::Decimal>()
+            .or_else(|_| rust_decimal::Decimal::from_scientific(v))
+            .ok()
+            .map(driver::Value::Decimal),
+        #[cfg(feature = "uuid")]
+        (driver::ColumnType::Uuid, driver::Value::String(v)) => {
+            v.parse().ok().map(driver::Value::Uuid)
+        }
+        #[cfg(feature = "uuid")]
+        (driver::ColumnType::Uuid, driver::Value::Bytes(v)) => {
+            uuid::Uuid::from_slice(v).ok().map(driver::Value::Uuid)
+        }
+        #[cfg(feature = "json")]
+        (driver::ColumnType::Json, driver::Value::String(v)) => {
+            serde_json::from_str(v).ok().map(driver::Value::Json)
+        }
+        #[cfg(feature = "json")]
+        (driver::ColumnType::Json, driver::Value::I64(v)) => Some(driver::Value::Json((*v).into())),
+        #[cfg(feature = "json")]
+        (driver::ColumnType::Json, driver::Value::F64(v)) => {
+            serde_json::Number::from_f64(*v).map(|v| driver::Value::Json(v.into()))
+        }
+        _ => None,
+    };
+
+    decoded.ok_or_else(|| Sqlite3Error::DecodeError(value, column_type).into())
+}
+
+/// Decode TEXT, unix time INTEGER or julian day REAL as UTC date and time
+#[cfg(feature = "chrono")]
+fn decode_datetime(value: &driver::Value) -> Option<chrono::NaiveDateTime> {
+    match value {
+        driver::Value::String(v) => {
+            for format in [
+                DATETIME_FORMAT,
+                "%Y-%m-%dT%H:%M:%S%.f",
+                "%Y-%m-%d %H:%M",
+                "%Y-%m-%dT%H:%M",
+            ] {
+                if let Ok(datetime) = chrono::NaiveDateTime::parse_from_str(v, format) {
+                    return Some(datetime);
+                }
+            }
+
+            if let Ok(date) = chrono::NaiveDate::parse_from_str(v, DATE_FORMAT) {
+                return date.and_hms_opt(0, 0, 0);
+            }
+
+            chrono::DateTime::parse_from_rfc3339(v)
+                .ok()
+                .map(|v| v.naive_utc())
+        }
+        driver::Value::I64(v) => chrono::DateTime::from_timestamp(*v, 0).map(|v| v.naive_utc()),
+        driver::Value::F64(v) => {
+            let millis = ((v - UNIX_EPOCH_JULIAN_DAY) * 86_400_000.0).round() as i64;
+
+            chrono::DateTime::from_timestamp_millis(millis).map(|v| v.naive_utc())
+        }
+        _ => None,
+    }
+}